@@ -4,9 +4,9 @@ extern crate test;
 extern crate boknafisk;
 
 use test::Bencher;
-use boknafisk::bit_boards::{bit_to_str, BISHOP_POST_MASKS};
+use boknafisk::move_gen::BISHOP_MASKS;
 
 #[bench]
 fn bench_(b: &mut test::Bencher) {
-    b.iter(|| bit_to_str(&(*BISHOP_POST_MASKS)[23]))
-}
\ No newline at end of file
+    b.iter(|| BISHOP_MASKS[23].to_string())
+}
@@ -0,0 +1,73 @@
+#![feature(test)]
+extern crate test;
+
+extern crate boknafisk;
+
+use test::Bencher;
+use boknafisk::bit_boards::{BitBoard, EMPTY};
+use boknafisk::move_gen::{BitboardGenerator, MagicAttackBoard};
+use boknafisk::piece::SliderType;
+use boknafisk::rkiss::RKISS;
+use boknafisk::sliding_attacks::{HyperbolaAttackBoard, KoggeStoneAttackBoard, PextAttackBoard};
+use boknafisk::square_position::SquarePosition;
+
+fn occupancies() -> Vec<BitBoard> {
+    let mut rkiss = RKISS::new(3);
+    (0..256).map(|_| BitBoard(rkiss.rand() & rkiss.rand())).collect()
+}
+
+fn bench_generator<G: BitboardGenerator>(b: &mut Bencher, generator: &G) {
+    let occupancies = occupancies();
+    let positions: Vec<SquarePosition> =
+        (0..64).map(|index| SquarePosition::new(index / 8, index % 8)).collect();
+
+    b.iter(|| {
+        let mut result = EMPTY;
+        for &occupancy in occupancies.iter() {
+            for &position in positions.iter() {
+                result ^= generator.get_attacks(position, occupancy);
+            }
+        }
+        result
+    })
+}
+
+#[bench]
+fn magic_rook(b: &mut Bencher) {
+    bench_generator(b, &MagicAttackBoard::new(SliderType::Rook))
+}
+
+#[bench]
+fn magic_bishop(b: &mut Bencher) {
+    bench_generator(b, &MagicAttackBoard::new(SliderType::Bishop))
+}
+
+#[bench]
+fn kogge_stone_rook(b: &mut Bencher) {
+    bench_generator(b, &KoggeStoneAttackBoard::new(SliderType::Rook))
+}
+
+#[bench]
+fn kogge_stone_bishop(b: &mut Bencher) {
+    bench_generator(b, &KoggeStoneAttackBoard::new(SliderType::Bishop))
+}
+
+#[bench]
+fn hyperbola_rook(b: &mut Bencher) {
+    bench_generator(b, &HyperbolaAttackBoard::new(SliderType::Rook))
+}
+
+#[bench]
+fn hyperbola_bishop(b: &mut Bencher) {
+    bench_generator(b, &HyperbolaAttackBoard::new(SliderType::Bishop))
+}
+
+#[bench]
+fn pext_rook(b: &mut Bencher) {
+    bench_generator(b, &PextAttackBoard::new(SliderType::Rook))
+}
+
+#[bench]
+fn pext_bishop(b: &mut Bencher) {
+    bench_generator(b, &PextAttackBoard::new(SliderType::Bishop))
+}
@@ -0,0 +1,235 @@
+//! Expands the magics stored in data/magics.csv into the sliding attack tables used by move_gen,
+//! so that the tables are embedded in the binary instead of being searched for at startup.
+//!
+//! A piece's magics are plain magics with a table per square unless its lines carry a fourth
+//! Offset column, in which case they are fixed-shift black magics indexing one shared table at
+//! those offsets, as written by `find_magics --black`.
+
+use std::env;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+/// The shifts of black magics, the same as find_magics searches with.
+const ROOK_BLACK_SHIFT: usize = 64 - 12;
+const BISHOP_BLACK_SHIFT: usize = 64 - 9;
+
+/// The magics read for one piece, with the offsets into the shared table of black magics.
+struct SliderMagics {
+    magics: [u64; 64],
+    offsets: [Option<usize>; 64],
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=data/magics.csv");
+
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let (rook_magics, bishop_magics) =
+        read_magics(&Path::new(&manifest_dir).join("data").join("magics.csv"));
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let out_file = File::create(&Path::new(&out_dir).join("magic_tables.rs")).unwrap();
+    let mut out = BufWriter::new(out_file);
+
+    write_tables(&mut out, "ROOK", &ROOK_DIRECTIONS, ROOK_BLACK_SHIFT, &rook_magics);
+    write_tables(&mut out, "BISHOP", &BISHOP_DIRECTIONS, BISHOP_BLACK_SHIFT, &bishop_magics);
+}
+
+fn read_magics(path: &Path) -> (SliderMagics, SliderMagics) {
+    let file = File::open(path).expect("Could not open data/magics.csv");
+    let mut rook_magics = SliderMagics {
+        magics: [0; 64],
+        offsets: [None; 64],
+    };
+    let mut bishop_magics = SliderMagics {
+        magics: [0; 64],
+        offsets: [None; 64],
+    };
+
+    for (line_index, line) in BufReader::new(file).lines().enumerate().skip(1) {
+        let line = line.unwrap();
+        let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+        if fields.len() != 3 && fields.len() != 4 {
+            panic!("Line {} of data/magics.csv should have 3 or 4 fields", line_index + 1);
+        }
+
+        let square_index = parse_square(fields[1])
+            .unwrap_or_else(|| panic!("Invalid square on line {} of data/magics.csv",
+                                      line_index + 1));
+        let magic: u64 = fields[2]
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid magic on line {} of data/magics.csv",
+                                       line_index + 1));
+        let offset = fields.get(3).map(|field| {
+            field.parse::<usize>()
+                .unwrap_or_else(|_| panic!("Invalid offset on line {} of data/magics.csv",
+                                           line_index + 1))
+        });
+
+        let slider_magics = match fields[0] {
+            "rook" => &mut rook_magics,
+            "bishop" => &mut bishop_magics,
+            piece => panic!("Unknown piece \"{}\" in data/magics.csv", piece),
+        };
+        slider_magics.magics[square_index] = magic;
+        slider_magics.offsets[square_index] = offset;
+    }
+
+    (rook_magics, bishop_magics)
+}
+
+fn parse_square(square: &str) -> Option<usize> {
+    let bytes = square.as_bytes();
+    if bytes.len() != 2 || bytes[0] < b'a' || bytes[0] > b'h' || bytes[1] < b'1' ||
+       bytes[1] > b'8' {
+        return None;
+    }
+
+    Some((bytes[1] - b'1') as usize * 8 + (bytes[0] - b'a') as usize)
+}
+
+fn write_tables<W: Write>(out: &mut W,
+                          name: &str,
+                          directions: &[(i32, i32)],
+                          black_shift: usize,
+                          slider_magics: &SliderMagics) {
+    let magics = &slider_magics.magics;
+    let black = slider_magics.offsets.iter().any(|offset| offset.is_some());
+    let mut masks = [0u64; 64];
+    let mut shifts = [0usize; 64];
+    let mut offsets = [0usize; 64];
+    let mut attacks: Vec<u64> = Vec::new();
+
+    for square_index in 0..64 {
+        let magic = magics[square_index];
+        if magic == 0 {
+            panic!("No {} magic for square {} in data/magics.csv",
+                   name.to_lowercase(),
+                   square_index);
+        }
+
+        masks[square_index] = relevant_mask(square_index, directions);
+
+        if black {
+            shifts[square_index] = black_shift;
+            offsets[square_index] = slider_magics.offsets[square_index]
+                .unwrap_or_else(|| {
+                    panic!("No offset for the {} magic for square {} in data/magics.csv",
+                           name.to_lowercase(),
+                           square_index)
+                });
+        } else {
+            shifts[square_index] = 64 - masks[square_index].count_ones() as usize;
+            offsets[square_index] = attacks.len();
+
+            let size = 1 << masks[square_index].count_ones();
+            attacks.resize(offsets[square_index] + size, 0);
+        }
+
+        // Carry-rippler enumeration of every subset of the mask.
+        let mut subset = 0u64;
+        loop {
+            // Black magics see every square outside the mask as occupied.
+            let key = if black { subset | !masks[square_index] } else { subset };
+            let index = offsets[square_index] +
+                        (key.wrapping_mul(magic) >> shifts[square_index]) as usize;
+            let attack = sliding_attack(square_index, directions, subset);
+
+            if index >= attacks.len() {
+                attacks.resize(index + 1, 0);
+            }
+            if attacks[index] != 0 && attacks[index] != attack {
+                panic!("The {} magic for square {} in data/magics.csv has a destructive \
+                        collision",
+                       name.to_lowercase(),
+                       square_index);
+            }
+            attacks[index] = attack;
+
+            subset = subset.wrapping_sub(masks[square_index]) & masks[square_index];
+            if subset == 0 {
+                break;
+            }
+        }
+    }
+
+    // Plain tables always come to the full size, black ones to wherever the last entry landed.
+    let attacks_size = if black {
+        attacks.len().to_string()
+    } else {
+        format!("{}_ATTACKS_SIZE", name)
+    };
+
+    writeln!(out, "pub const {}_BLACK: bool = {};", name, black).unwrap();
+    write_array(out, &format!("{}_MAGICS", name), "u64", "64", magics);
+    write_array(out, &format!("{}_MASKS", name), "BitBoard", "64", &masks);
+    write_array(out, &format!("{}_SHIFTS", name), "usize", "64", &shifts);
+    write_array(out, &format!("{}_OFFSETS", name), "usize", "64", &offsets);
+    write_array(out, &format!("{}_ATTACKS", name), "BitBoard", &attacks_size, &attacks);
+}
+
+fn write_array<W: Write, T: std::fmt::LowerHex>(out: &mut W,
+                                                name: &str,
+                                                element_type: &str,
+                                                length: &str,
+                                                values: &[T]) {
+    writeln!(out, "pub static {}: [{}; {}] = [", name, element_type, length).unwrap();
+    for value in values {
+        if element_type == "BitBoard" {
+            writeln!(out, "    BitBoard({:#x}),", value).unwrap();
+        } else {
+            writeln!(out, "    {:#x},", value).unwrap();
+        }
+    }
+    writeln!(out, "];").unwrap();
+}
+
+/// The squares whose occupancy affects a slider's attacks, which excludes the last square of each
+/// ray.
+fn relevant_mask(square_index: usize, directions: &[(i32, i32)]) -> u64 {
+    let mut mask = 0;
+
+    for &(rank_step, file_step) in directions {
+        let mut rank = (square_index / 8) as i32 + rank_step;
+        let mut file = (square_index % 8) as i32 + file_step;
+
+        while on_board(rank + rank_step, file + file_step) {
+            mask |= 1 << (rank * 8 + file);
+            rank += rank_step;
+            file += file_step;
+        }
+    }
+
+    mask
+}
+
+fn sliding_attack(square_index: usize, directions: &[(i32, i32)], occupancy: u64) -> u64 {
+    let mut attack = 0;
+
+    for &(rank_step, file_step) in directions {
+        let mut rank = (square_index / 8) as i32 + rank_step;
+        let mut file = (square_index % 8) as i32 + file_step;
+
+        while on_board(rank, file) {
+            let square = 1 << (rank * 8 + file);
+            attack |= square;
+
+            if (occupancy & square) != 0 {
+                break;
+            }
+            rank += rank_step;
+            file += file_step;
+        }
+    }
+
+    attack
+}
+
+#[inline]
+fn on_board(rank: i32, file: i32) -> bool {
+    0 <= rank && rank < 8 && 0 <= file && file < 8
+}
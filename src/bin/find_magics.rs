@@ -0,0 +1,498 @@
+//! Searches for rook and bishop magics and writes them in the data/magics.csv format.
+//!
+//! By default every square gets a plain magic, indexing `occupancy & mask` with a shift of 64
+//! minus the number of mask bits. Each square's table then has a fixed size, so the first magic
+//! that works is as good as any other.
+//!
+//! With `--black` the search looks for fixed-shift black magics instead, which index
+//! `occupancy | !mask`, and overlaps the per-square tables wherever their entries agree. Of
+//! `--tries` working magics for each square the one that grows the shared table least is kept
+//! (1000 by default). Black magics are written with an extra Offset column giving where each
+//! square's index 0 falls in the shared table.
+//!
+//! A black magic search only counts when its shared table comes out smaller than the embedded
+//! one. A piece whose search falls short keeps its embedded magics, and when neither piece
+//! improves nothing is written and the exit status is 1. Black magics only pay off once most
+//! squares share a large part of their tables with others, and the default 1000 tries per square
+//! still leaves both tables larger than the plain ones. Expect `--black` to succeed only on long
+//! runs with many more tries, and to exit with 1 otherwise.
+//!
+//! Usage: find_magics [--black [--tries N]] [--seed N] [--output PATH]
+
+extern crate boknafisk;
+
+use std::cmp;
+use std::env;
+use std::fs::File;
+use std::io::{self, Write};
+use std::process;
+
+use boknafisk::bit_boards::subsets_iterator;
+use boknafisk::move_gen::{MagicAttackBoard, generate_mask, generate_sliding_attack_bitboards};
+use boknafisk::piece::SliderType;
+use boknafisk::rkiss::{MAGIC_BOOSTERS, RKISS};
+use boknafisk::square_position::{CARDINAL, Direction, INTERMEDIATE, SquarePosition};
+
+/// The shifts build.rs uses for black magics.
+const ROOK_BLACK_SHIFT: usize = 64 - 12;
+const BISHOP_BLACK_SHIFT: usize = 64 - 9;
+
+struct Options {
+    black: bool,
+    tries: Option<usize>,
+    seed: usize,
+    output: Option<String>,
+}
+
+struct SquareMagic {
+    magic: u64,
+    offset: usize,
+}
+
+/// The occupancy keys a magic is multiplied with and the attacks they must map to.
+struct SquareTable {
+    keys: Vec<u64>,
+    attacks: Vec<u64>,
+}
+
+fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(message) => {
+            writeln!(io::stderr(), "{}", message).unwrap();
+            writeln!(io::stderr(),
+                     "Usage: find_magics [--black [--tries N]] [--seed N] [--output PATH]")
+                .unwrap();
+            process::exit(1);
+        }
+    };
+
+    let mut rkiss = RKISS::new(options.seed);
+    let searches = [(SliderType::Rook, "rook", &CARDINAL, ROOK_BLACK_SHIFT),
+                    (SliderType::Bishop, "bishop", &INTERMEDIATE, BISHOP_BLACK_SHIFT)];
+
+    let mut csv = String::from(if options.black {
+        "Piece, Square, Magic, Offset\n"
+    } else {
+        "Piece, Square, Magic\n"
+    });
+    let mut improved = false;
+
+    for &(slider_type, name, directions, black_shift) in searches.iter() {
+        let current = MagicAttackBoard::new(slider_type);
+        let current_size = current.attacks().len();
+
+        let (magics, table_size, black) = if options.black {
+            match find_black_magics(&mut rkiss,
+                                    directions,
+                                    black_shift,
+                                    options.tries.unwrap_or(1000),
+                                    current_size) {
+                Some((magics, table_size)) => {
+                    improved = true;
+                    (magics, table_size, true)
+                }
+                None => {
+                    writeln!(io::stderr(),
+                             "{} table: no black magics fit in under {} entries, keeping the \
+                              embedded magics",
+                             name,
+                             current_size)
+                        .unwrap();
+                    (embedded_magics(&current), current_size, current.is_black())
+                }
+            }
+        } else {
+            let (magics, table_size) = find_plain_magics(&mut rkiss, directions);
+            (magics, table_size, false)
+        };
+
+        for (square_index, square_magic) in magics.iter().enumerate() {
+            let position = SquarePosition::new(square_index / 8, square_index % 8);
+
+            if black {
+                csv += &format!("{}, {}, {}, {}\n",
+                                name,
+                                position,
+                                square_magic.magic,
+                                square_magic.offset);
+            } else {
+                csv += &format!("{}, {}, {}\n", name, position, square_magic.magic);
+            }
+        }
+
+        writeln!(io::stderr(),
+                 "{} table: {} entries ({} KiB), current size {} entries ({} KiB)",
+                 name,
+                 table_size,
+                 table_size * 8 / 1024,
+                 current_size,
+                 current_size * 8 / 1024)
+            .unwrap();
+    }
+
+    if options.black && !improved {
+        writeln!(io::stderr(), "No table got smaller, so no magics were written").unwrap();
+        process::exit(1);
+    }
+
+    let written = match options.output {
+        Some(ref path) => File::create(path).and_then(|mut file| file.write_all(csv.as_bytes())),
+        None => io::stdout().write_all(csv.as_bytes()),
+    };
+
+    if let Err(err) = written {
+        writeln!(io::stderr(), "Could not write magics: {}", err).unwrap();
+        process::exit(1);
+    }
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        black: false,
+        tries: None,
+        seed: 203,
+        output: None,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--black" => options.black = true,
+            "--tries" | "--seed" | "--output" => {
+                let value = match args.next() {
+                    Some(value) => value,
+                    None => return Err(format!("Missing value for {}", arg)),
+                };
+
+                match arg.as_str() {
+                    "--output" => options.output = Some(value),
+                    _ => {
+                        let number = value.parse::<usize>()
+                            .map_err(|_| format!("Invalid number \"{}\" for {}", value, arg))?;
+                        if arg == "--tries" {
+                            options.tries = Some(number);
+                        } else {
+                            options.seed = number;
+                        }
+                    }
+                }
+            }
+            _ => return Err(format!("Unknown argument \"{}\"", arg)),
+        }
+    }
+
+    match options.tries {
+        Some(_) if !options.black => {
+            return Err("--tries only applies to black magic searches (--black)".to_owned())
+        }
+        Some(0) => return Err("--tries must be at least 1".to_owned()),
+        _ => {}
+    }
+
+    Ok(options)
+}
+
+fn square_table(square_index: usize, directions: &[Direction], black: bool) -> SquareTable {
+    let mask = generate_mask(square_index, directions, false);
+    let position = SquarePosition::new(square_index / 8, square_index % 8);
+
+    let keys = subsets_iterator(&mask)
+        .map(|subset| if black { subset | !mask } else { subset })
+        .map(|key| key.bits())
+        .collect();
+    let attacks = generate_sliding_attack_bitboards(mask, directions, position)
+        .iter()
+        .map(|attack| attack.bits())
+        .collect();
+
+    SquareTable {
+        keys: keys,
+        attacks: attacks,
+    }
+}
+
+/// Checks that `magic` maps every key to an index holding its attack, leaving the attacks in
+/// `scratch` by index and 0 elsewhere. `scratch` must have room for every index the shift allows.
+fn try_magic(magic: u64, shift: usize, table: &SquareTable, scratch: &mut [u64]) -> bool {
+    for entry in scratch.iter_mut() {
+        *entry = 0;
+    }
+
+    for (&key, &attack) in table.keys.iter().zip(table.attacks.iter()) {
+        let index = (key.wrapping_mul(magic) >> shift) as usize;
+
+        if scratch[index] != 0 && scratch[index] != attack {
+            return false;
+        }
+
+        scratch[index] = attack;
+    }
+
+    true
+}
+
+/// The magics and offsets of the tables build.rs embedded.
+fn embedded_magics(board: &MagicAttackBoard) -> Vec<SquareMagic> {
+    board.magics()
+        .iter()
+        .zip(board.offsets().iter())
+        .map(|(&magic, &offset)| {
+            SquareMagic {
+                magic: magic,
+                offset: offset,
+            }
+        })
+        .collect()
+}
+
+fn find_plain_magics(rkiss: &mut RKISS, directions: &[Direction]) -> (Vec<SquareMagic>, usize) {
+    let mut magics = Vec::with_capacity(64);
+    let mut table_size = 0;
+
+    for square_index in 0..64 {
+        let booster = MAGIC_BOOSTERS[square_index / 8];
+        let mask = generate_mask(square_index, directions, false);
+        let shift = 64 - mask.len();
+        let table = square_table(square_index, directions, false);
+        let mut scratch = vec![0 as u64; 1 << (64 - shift)];
+
+        let mut magic;
+        loop {
+            magic = rkiss.magic_rand(booster);
+
+            // Magics that spread few mask bits into the top byte rarely work.
+            if (magic.wrapping_mul(mask.bits()) >> 56).count_ones() >= 6 &&
+               try_magic(magic, shift, &table, &mut scratch) {
+                break;
+            }
+        }
+
+        magics.push(SquareMagic {
+            magic: magic,
+            offset: table_size,
+        });
+        table_size += 1 << (64 - shift);
+    }
+
+    (magics, table_size)
+}
+
+/// Searches black magics for every square and packs their tables into one shared table, which
+/// must end up smaller than `limit` entries. Returns `None` when some square cannot be fitted in
+/// below the limit.
+fn find_black_magics(rkiss: &mut RKISS,
+                     directions: &[Direction],
+                     shift: usize,
+                     tries: usize,
+                     limit: usize)
+                     -> Option<(Vec<SquareMagic>, usize)> {
+    let tables: Vec<SquareTable> = (0..64)
+        .map(|square_index| square_table(square_index, directions, true))
+        .collect();
+
+    // Squares with the most relevant occupancies are the hardest to fit, so they go first.
+    let mut order: Vec<usize> = (0..64).collect();
+    order.sort_by(|&a, &b| tables[b].keys.len().cmp(&tables[a].keys.len()));
+
+    let mut shared = SharedTable::new();
+    let mut magics: Vec<SquareMagic> = (0..64)
+        .map(|_| {
+            SquareMagic {
+                magic: 0,
+                offset: 0,
+            }
+        })
+        .collect();
+    let mut scratch = vec![0 as u64; 1 << (64 - shift)];
+
+    for square_index in order {
+        let booster = MAGIC_BOOSTERS[square_index / 8];
+        let table = &tables[square_index];
+
+        // The best placement so far as (magic, offset, entries, end of the shared table).
+        let mut best: Option<(u64, usize, Vec<(usize, u64)>, usize)> = None;
+        let mut found = 0;
+
+        while found < tries {
+            let magic = rkiss.magic_rand(booster);
+            if !try_magic(magic, shift, table, &mut scratch) {
+                continue;
+            }
+            found += 1;
+
+            let entries: Vec<(usize, u64)> = scratch.iter()
+                .enumerate()
+                .filter(|&(_, &attack)| attack != 0)
+                .map(|(index, &attack)| (index, attack))
+                .collect();
+
+            // Only placements that end before the best one so far, and before the limit, count.
+            let bound = match best {
+                Some((_, _, _, best_end)) => best_end,
+                None => limit,
+            };
+
+            if let Some(offset) = shared.best_fit(&entries, bound) {
+                let end = cmp::max(shared.len(), offset + entries[entries.len() - 1].0 + 1);
+                best = Some((magic, offset, entries, end));
+            }
+        }
+
+        let (magic, offset, entries, _) = match best {
+            Some(best) => best,
+            None => return None,
+        };
+        shared.insert(offset, &entries);
+
+        magics[square_index] = SquareMagic {
+            magic: magic,
+            offset: offset,
+        };
+    }
+
+    Some((magics, shared.len()))
+}
+
+/// The attack table shared by every square, together with a bitmap of the entries in use so that
+/// whole words of candidate entries can be checked against it at once.
+struct SharedTable {
+    attacks: Vec<u64>,
+    used: Vec<u64>,
+}
+
+impl SharedTable {
+    fn new() -> SharedTable {
+        SharedTable {
+            attacks: Vec::new(),
+            used: Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.attacks.len()
+    }
+
+    /// The 64 bits of the used bitmap starting at entry `start`.
+    fn used_word(&self, start: usize) -> u64 {
+        let word = start / 64;
+        let bit = start % 64;
+        let low = self.used.get(word).map_or(0, |&used| used >> bit);
+        let high = if bit == 0 {
+            0
+        } else {
+            self.used.get(word + 1).map_or(0, |&used| used << (64 - bit))
+        };
+
+        low | high
+    }
+
+    /// Whether `entries`, given as their bitmap in 64 entry words, fit at `offset`: every entry
+    /// must land on an unused entry or one holding the same attack.
+    fn fits(&self, offset: usize, entries: &[(usize, u64)], bitmap: &[u64]) -> bool {
+        let clashes = bitmap.iter()
+            .enumerate()
+            .any(|(word, &bits)| bits & self.used_word(offset + word * 64) != 0);
+
+        !clashes ||
+        entries.iter().all(|&(index, attack)| {
+            let shared_index = offset + index;
+            shared_index >= self.attacks.len() || self.attacks[shared_index] == 0 ||
+            self.attacks[shared_index] == attack
+        })
+    }
+
+    /// The offset for `entries`, which must be sorted by index, that leaves the table shortest,
+    /// provided it ends before `bound`.
+    fn best_fit(&self, entries: &[(usize, u64)], bound: usize) -> Option<usize> {
+        let highest = entries[entries.len() - 1].0;
+        let mut bitmap = vec![0u64; highest / 64 + 1];
+        for &(index, _) in entries {
+            bitmap[index / 64] |= 1 << (index % 64);
+        }
+
+        // The end of the table never shrinks as the offset grows, so the first offset that fits
+        // is the best one.
+        let mut offset = 0;
+        while cmp::max(self.len(), offset + highest + 1) < bound {
+            if self.fits(offset, entries, &bitmap) {
+                return Some(offset);
+            }
+            offset += 1;
+        }
+
+        None
+    }
+
+    fn insert(&mut self, offset: usize, entries: &[(usize, u64)]) {
+        let end = offset + entries[entries.len() - 1].0 + 1;
+        if end > self.attacks.len() {
+            self.attacks.resize(end, 0);
+            self.used.resize(end / 64 + 1, 0);
+        }
+
+        for &(index, attack) in entries {
+            self.attacks[offset + index] = attack;
+            self.used[(offset + index) / 64] |= 1 << ((offset + index) % 64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::usize;
+
+    use boknafisk::rkiss::RKISS;
+    use boknafisk::square_position::INTERMEDIATE;
+
+    use {BISHOP_BLACK_SHIFT, SharedTable, find_black_magics, square_table};
+
+    #[test]
+    fn shared_table_best_fit_test() {
+        let mut shared = SharedTable::new();
+        assert_eq!(shared.best_fit(&[(0, 1), (2, 2)], 100), Some(0));
+        shared.insert(0, &[(0, 1), (2, 2)]);
+        assert_eq!(shared.len(), 3);
+
+        // Entries may share a slot holding the same attack, or fill the gaps between others.
+        assert_eq!(shared.best_fit(&[(0, 1), (1, 5)], 100), Some(0));
+        assert_eq!(shared.best_fit(&[(0, 6)], 100), Some(1));
+
+        // Clashing attacks push the entries past the end of the table, unless that reaches the
+        // bound.
+        assert_eq!(shared.best_fit(&[(0, 3), (1, 4)], 100), Some(3));
+        assert_eq!(shared.best_fit(&[(0, 3), (1, 4)], 5), None);
+        shared.insert(3, &[(0, 3), (1, 4)]);
+        assert_eq!(shared.len(), 5);
+
+        // Offsets past the first 64 entries are checked against the right bitmap words.
+        let entries: Vec<(usize, u64)> = (0..70).map(|index| (index, 7)).collect();
+        assert_eq!(shared.best_fit(&entries, 100), Some(5));
+        shared.insert(5, &entries);
+        assert_eq!(shared.best_fit(&[(0, 7), (1, 8)], 100), Some(74));
+    }
+
+    #[test]
+    fn black_magic_packing_test() {
+        let mut rkiss = RKISS::new(203);
+        let (magics, table_size) =
+            find_black_magics(&mut rkiss, &INTERMEDIATE, BISHOP_BLACK_SHIFT, 2, usize::MAX)
+                .unwrap();
+        let mut shared = vec![0 as u64; table_size];
+
+        // Every key of every square must land inside the table on its own attack, or on one
+        // another square wrote that agrees with it.
+        for (square_index, square_magic) in magics.iter().enumerate() {
+            let table = square_table(square_index, &INTERMEDIATE, true);
+
+            for (&key, &attack) in table.keys.iter().zip(table.attacks.iter()) {
+                let index = square_magic.offset +
+                            (key.wrapping_mul(square_magic.magic) >> BISHOP_BLACK_SHIFT) as usize;
+
+                assert!(index < table_size);
+                assert!(shared[index] == 0 || shared[index] == attack);
+                shared[index] = attack;
+            }
+        }
+    }
+}
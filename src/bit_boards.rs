@@ -1,15 +1,22 @@
-use std::convert::From;
+use std::convert::{From, TryFrom};
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, Shr};
 
-use unicode_segmentation::UnicodeSegmentation;
-
-use square_position::SquarePosition;
+use square_position::{Direction, SquarePosition};
 use piece_board::PieceBoard;
 
-pub type BitBoard = u64;
+/// A set of squares, one bit per square with a1 as the least significant bit and h8 as the most.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BitBoard(pub u64);
+
+pub const EMPTY: BitBoard = BitBoard(0);
+pub const FULL: BitBoard = BitBoard(!0);
+
+const FILE_A: u64 = 0x0101010101010101;
 
 lazy_static! {
     pub static ref FILE_BOARDS: [BitBoard; 8] = {
-        let mut file_boards = [0x101010101010101 as BitBoard; 8];
+        let mut file_boards = [BitBoard(FILE_A); 8];
 
         for index in 0..file_boards.len() {
             file_boards[index] = file_boards[index] << index;
@@ -18,7 +25,7 @@ lazy_static! {
         file_boards
     };
     pub static ref RANK_BOARDS: [BitBoard; 8] = {
-        let mut rank_boards = [0xff as BitBoard; 8];
+        let mut rank_boards = [BitBoard(0xff); 8];
 
         for index in 0..rank_boards.len() {
             rank_boards[index] = rank_boards[index] << (8 * index);
@@ -43,78 +50,265 @@ const INDEX_64: [usize; 64] = [
 const DEBRUIJIN_64: u64 = 0x03f79d71b4cb0a89;
 
 pub fn bit_scan_forward(board: BitBoard) -> Option<usize> {
-    if board == 0 {
+    let BitBoard(bits) = board;
+
+    if bits == 0 {
         None
     } else {
-        Some(INDEX_64[((board ^ (board - 1)).wrapping_mul(DEBRUIJIN_64) >> 58) as usize])
+        Some(INDEX_64[((bits ^ (bits - 1)).wrapping_mul(DEBRUIJIN_64) >> 58) as usize])
     }
 }
 
-pub fn bit_scan_reverse(mut board: BitBoard) -> Option<usize> {
-    if board == 0 {
+pub fn bit_scan_reverse(board: BitBoard) -> Option<usize> {
+    let BitBoard(mut bits) = board;
+
+    if bits == 0 {
         None
     } else {
-        board |= board >> 1;
-        board |= board >> 2;
-        board |= board >> 4;
-        board |= board >> 8;
-        board |= board >> 16;
-        board |= board >> 32;
+        bits |= bits >> 1;
+        bits |= bits >> 2;
+        bits |= bits >> 4;
+        bits |= bits >> 8;
+        bits |= bits >> 16;
+        bits |= bits >> 32;
+
+        Some(INDEX_64[(bits.wrapping_mul(DEBRUIJIN_64) >> 58) as usize])
+    }
+}
+
+impl BitBoard {
+    #[inline]
+    pub fn new(bits: u64) -> BitBoard {
+        BitBoard(bits)
+    }
+
+    #[inline]
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    #[inline]
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The number of squares in the set.
+    #[inline]
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    #[inline]
+    pub fn contains(self, position: SquarePosition) -> bool {
+        (self.0 >> position.to_square_index()) & 1 != 0
+    }
+
+    /// The lowest square in the set.
+    #[inline]
+    pub fn lsb(self) -> Option<SquarePosition> {
+        bit_scan_forward(self).map(|square_index| SquarePosition::try_from(square_index).unwrap())
+    }
+
+    /// Removes the lowest square from the set and returns it.
+    #[inline]
+    pub fn pop_lsb(&mut self) -> Option<SquarePosition> {
+        let lowest = self.lsb();
+        self.0 &= self.0.wrapping_sub(1);
+        lowest
+    }
+
+    /// Moves every square one step of `direction`, dropping squares that would leave the board
+    /// rather than letting them wrap around to the other side.
+    #[inline]
+    pub fn shift(self, direction: Direction) -> BitBoard {
+        let Direction(rank_step, file_step) = direction;
+        if rank_step.abs() > 7 || file_step.abs() > 7 {
+            return EMPTY;
+        }
+
+        let amount = 8 * rank_step + file_step;
+        let shifted = if amount >= 0 {
+            self.0 << amount
+        } else {
+            self.0 >> -amount
+        };
+
+        // Squares can only land on these files without wrapping around an edge.
+        let files = if file_step >= 0 {
+            (0xffu64 << file_step) & 0xff
+        } else {
+            0xffu64 >> -file_step
+        };
+
+        BitBoard(shifted & files.wrapping_mul(FILE_A))
+    }
+
+    /// Mirrors the board top to bottom, so a1 becomes a8.
+    #[inline]
+    pub fn flip_vertical(self) -> BitBoard {
+        BitBoard(self.0.swap_bytes())
+    }
+
+    /// Mirrors the board left to right, so a1 becomes h1.
+    #[inline]
+    pub fn mirror_horizontal(self) -> BitBoard {
+        let mut bits = self.0;
+        bits = ((bits >> 1) & 0x5555555555555555) | ((bits & 0x5555555555555555) << 1);
+        bits = ((bits >> 2) & 0x3333333333333333) | ((bits & 0x3333333333333333) << 2);
+        bits = ((bits >> 4) & 0x0f0f0f0f0f0f0f0f) | ((bits & 0x0f0f0f0f0f0f0f0f) << 4);
+        BitBoard(bits)
+    }
+
+    /// Mirrors the board in the a1-h8 diagonal, so a8 becomes h1.
+    #[inline]
+    pub fn flip_diagonal(self) -> BitBoard {
+        let mut bits = self.0;
+        let mut swapped = 0x0f0f0f0f00000000 & (bits ^ (bits << 28));
+        bits ^= swapped ^ (swapped >> 28);
+        swapped = 0x3333000033330000 & (bits ^ (bits << 14));
+        bits ^= swapped ^ (swapped >> 14);
+        swapped = 0x5500550055005500 & (bits ^ (bits << 7));
+        bits ^= swapped ^ (swapped >> 7);
+        BitBoard(bits)
+    }
+
+    /// Mirrors the board in the a8-h1 diagonal, so a1 becomes h8.
+    #[inline]
+    pub fn flip_anti_diagonal(self) -> BitBoard {
+        self.flip_diagonal().rotate_180()
+    }
+
+    #[inline]
+    pub fn rotate_180(self) -> BitBoard {
+        self.flip_vertical().mirror_horizontal()
+    }
+
+    /// Rotates the board a quarter turn clockwise, so a1 becomes a8.
+    #[inline]
+    pub fn rotate_clockwise(self) -> BitBoard {
+        self.flip_diagonal().flip_vertical()
+    }
 
-        Some(INDEX_64[(board.wrapping_mul(DEBRUIJIN_64) >> 58) as usize])
+    /// Rotates the board a quarter turn anticlockwise, so a1 becomes h1.
+    #[inline]
+    pub fn rotate_anticlockwise(self) -> BitBoard {
+        self.flip_vertical().flip_diagonal()
+    }
+
+    #[inline]
+    pub fn squares(self) -> Squares {
+        Squares { board: self }
     }
 }
 
-pub struct BitBoardIter<'iter> {
-    board: &'iter BitBoard,
-    mask: BitBoard,
+/// Yields the squares of a board from a1 to h8, consuming a copy of it as it goes.
+#[derive(Debug, Clone)]
+pub struct Squares {
+    board: BitBoard,
 }
 
-impl<'iter> Iterator for BitBoardIter<'iter> {
-    type Item = usize;
+impl Iterator for Squares {
+    type Item = SquarePosition;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let square_index = bit_scan_forward(*self.board & !self.mask);
-        self.mask |= 1 << square_index.unwrap_or(0);
-        square_index
+    #[inline]
+    fn next(&mut self) -> Option<SquarePosition> {
+        self.board.pop_lsb()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.board.len();
+        (len, Some(len))
     }
 }
 
-pub fn bit_iterator<'a>(board: &'a BitBoard) -> BitBoardIter<'a> {
-    BitBoardIter {
-        board: board,
-        mask: 0,
+impl ExactSizeIterator for Squares {}
+
+impl IntoIterator for BitBoard {
+    type Item = SquarePosition;
+    type IntoIter = Squares;
+
+    #[inline]
+    fn into_iter(self) -> Squares {
+        self.squares()
     }
 }
 
-pub fn bit_to_str(&board: &BitBoard) -> String {
-    let mut output = format!("{:064b}", board);
+macro_rules! impl_bit_operator {
+    ($operator:ident, $method:ident, $assign_operator:ident, $assign_method:ident) => {
+        impl $operator for BitBoard {
+            type Output = BitBoard;
+
+            #[inline]
+            fn $method(self, other: BitBoard) -> BitBoard {
+                BitBoard($operator::$method(self.0, other.0))
+            }
+        }
 
-    let mut index = 8;
-    for _ in 1..8 {
-        output.insert(index, '\n');
-        index += 9;
+        impl $assign_operator for BitBoard {
+            #[inline]
+            fn $assign_method(&mut self, other: BitBoard) {
+                $assign_operator::$assign_method(&mut self.0, other.0)
+            }
+        }
     }
+}
 
-    output = output.split_whitespace()
-        .map(|x| {
-            x.graphemes(true)
-                .rev()
-                .flat_map(|g| g.chars())
-                .collect::<String>() + "\n"
-        })
-        .collect::<String>();
+impl_bit_operator!(BitAnd, bitand, BitAndAssign, bitand_assign);
+impl_bit_operator!(BitOr, bitor, BitOrAssign, bitor_assign);
+impl_bit_operator!(BitXor, bitxor, BitXorAssign, bitxor_assign);
 
-    output = output.replace('0', ".");
-    output = output.replace("", " ");
+impl Not for BitBoard {
+    type Output = BitBoard;
 
-    output
+    #[inline]
+    fn not(self) -> BitBoard {
+        BitBoard(!self.0)
+    }
+}
+
+impl Shl<usize> for BitBoard {
+    type Output = BitBoard;
+
+    #[inline]
+    fn shl(self, amount: usize) -> BitBoard {
+        BitBoard(self.0 << amount)
+    }
+}
+
+impl Shr<usize> for BitBoard {
+    type Output = BitBoard;
+
+    #[inline]
+    fn shr(self, amount: usize) -> BitBoard {
+        BitBoard(self.0 >> amount)
+    }
+}
+
+/// Draws the board from white's side, rank 8 first, with `1` for squares in the set.
+impl fmt::Display for BitBoard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for rank in (0..8).rev() {
+            write!(f, "{}", rank + 1)?;
+            for file in 0..8 {
+                let square = if self.contains(SquarePosition::new(rank, file)) {
+                    '1'
+                } else {
+                    '.'
+                };
+                write!(f, " {}", square)?;
+            }
+            writeln!(f, "")?;
+        }
+
+        write!(f, "  a b c d e f g h")
+    }
 }
 
 #[derive(Debug)]
 pub struct BitSubsetIter<'a> {
     board: &'a BitBoard,
-    subset: BitBoard,
+    subset: u64,
     count: usize,
 }
 
@@ -122,15 +316,17 @@ impl<'a> Iterator for BitSubsetIter<'a> {
     type Item = BitBoard;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let BitBoard(set) = *self.board;
+
         if self.subset == 0 && self.count > 0 {
             None
-        } else if *self.board == 0 {
+        } else if set == 0 {
             None
         } else {
             let result = self.subset;
-            self.subset = (self.subset.wrapping_sub(*self.board)) & *self.board;
+            self.subset = (self.subset.wrapping_sub(set)) & set;
             self.count += 1;
-            Some(result)
+            Some(BitBoard(result))
         }
     }
 }
@@ -144,20 +340,21 @@ pub fn subsets_iterator(set: &BitBoard) -> BitSubsetIter {
 }
 
 impl From<SquarePosition> for BitBoard {
+    #[inline]
     fn from(position: SquarePosition) -> BitBoard {
-        1 << position.to_square_index()
+        BitBoard(1 << position.to_square_index())
     }
 }
 
 impl From<PieceBoard> for ([[BitBoard; 6]; 2], [BitBoard; 2]) {
     fn from(piece_board: PieceBoard) -> ([[BitBoard; 6]; 2], [BitBoard; 2]) {
-        let mut bit_board = [[0 as BitBoard; 6]; 2];
-        let mut bit_occupancy = [0 as BitBoard; 2];
+        let mut bit_board = [[EMPTY; 6]; 2];
+        let mut bit_occupancy = [EMPTY; 2];
 
         for (position, piece) in piece_board.into_iter() {
             bit_board[piece.color() as usize][piece.piece_type() as usize] |=
-                1 << position.to_square_index();
-            bit_occupancy[piece.color() as usize] |= 1 << position.to_square_index();
+                position.to_bit_board();
+            bit_occupancy[piece.color() as usize] |= position.to_bit_board();
         }
 
         (bit_board, bit_occupancy)
@@ -167,20 +364,108 @@ impl From<PieceBoard> for ([[BitBoard; 6]; 2], [BitBoard; 2]) {
 #[cfg(test)]
 mod test {
 
-    use bit_boards::{BitBoard, subsets_iterator};
+    use bit_boards::{BitBoard, EMPTY, FULL, subsets_iterator};
+    use square_position::{EAST, NORTH, SOUTH, SquarePosition, WEST};
+    use test_helpers::board;
 
     #[test]
     fn subset_iterator_contains_zero_test() {
-        let mask: BitBoard = 0x1010106e101000;
+        let mask = BitBoard(0x1010106e101000);
 
-        assert!(subsets_iterator(&mask).any(|x| x == 0));
+        assert!(subsets_iterator(&mask).any(|x| x == EMPTY));
     }
 
     #[test]
     fn subset_iterator_correct_len_test() {
-        let mask: BitBoard = 0x1010106e101000;
+        let mask = BitBoard(0x1010106e101000);
         let count_size: usize = subsets_iterator(&mask).count();
-        let expected_size: usize = 2u32.pow(mask.count_ones()) as usize;
+        let expected_size: usize = 2usize.pow(mask.len() as u32);
         assert_eq!(count_size, expected_size);
     }
+
+    #[test]
+    fn operators_test() {
+        let left = board(&["a1", "b2", "c3"]);
+        let right = board(&["b2", "h8"]);
+
+        assert_eq!(left & right, board(&["b2"]));
+        assert_eq!(left | right, board(&["a1", "b2", "c3", "h8"]));
+        assert_eq!(left ^ right, board(&["a1", "c3", "h8"]));
+        assert_eq!(!FULL, EMPTY);
+        assert_eq!((!left).len(), 61);
+        assert_eq!(board(&["a1"]) << 9, board(&["b2"]));
+        assert_eq!(board(&["h8"]) >> 63, board(&["a1"]));
+
+        let mut accumulated = left;
+        accumulated &= right;
+        accumulated |= board(&["d4"]);
+        accumulated ^= board(&["b2", "e5"]);
+        assert_eq!(accumulated, board(&["d4", "e5"]));
+    }
+
+    #[test]
+    fn squares_test() {
+        let mut squares = board(&["h8", "a1", "e4"]);
+
+        assert_eq!(squares.len(), 3);
+        assert_eq!(squares.into_iter().map(|square| square.to_string()).collect::<Vec<_>>(),
+                   vec!["a1", "e4", "h8"]);
+        assert_eq!(squares.squares().len(), 3);
+
+        assert_eq!(squares.pop_lsb(), Some(SquarePosition::new(0, 0)));
+        assert_eq!(squares.lsb(), Some(SquarePosition::new(3, 4)));
+        assert_eq!(squares, board(&["e4", "h8"]));
+        assert!(squares.contains(SquarePosition::new(7, 7)));
+        assert!(!squares.contains(SquarePosition::new(0, 0)));
+
+        assert_eq!(EMPTY.lsb(), None);
+        assert_eq!(EMPTY.into_iter().count(), 0);
+    }
+
+    #[test]
+    fn shift_test() {
+        let edges = board(&["a1", "h1", "a8", "h8", "d4"]);
+
+        assert_eq!(edges.shift(NORTH), board(&["a2", "h2", "d5"]));
+        assert_eq!(edges.shift(SOUTH), board(&["a7", "h7", "d3"]));
+        assert_eq!(edges.shift(EAST), board(&["b1", "b8", "e4"]));
+        assert_eq!(edges.shift(WEST), board(&["g1", "g8", "c4"]));
+        assert_eq!(edges.shift(NORTH + EAST), board(&["b2", "e5"]));
+        assert_eq!(edges.shift(SOUTH + WEST), board(&["g7", "c3"]));
+        assert_eq!(edges.shift(NORTH + NORTH + EAST), board(&["b3", "e6"]));
+        assert_eq!(edges.shift(WEST * 2 + SOUTH), board(&["f7", "b3"]));
+        assert_eq!(FULL.shift(EAST * 8), EMPTY);
+    }
+
+    #[test]
+    fn flips_test() {
+        let corner = board(&["a1", "b1", "a2"]);
+
+        assert_eq!(corner.flip_vertical(), board(&["a8", "b8", "a7"]));
+        assert_eq!(corner.mirror_horizontal(), board(&["h1", "g1", "h2"]));
+        assert_eq!(corner.rotate_180(), board(&["h8", "g8", "h7"]));
+        assert_eq!(board(&["b1", "e4"]).flip_diagonal(), board(&["a2", "d5"]));
+        assert_eq!(board(&["b1", "e4"]).flip_anti_diagonal(), board(&["h7", "e4"]));
+        assert_eq!(board(&["a1", "b1"]).rotate_clockwise(), board(&["a8", "a7"]));
+        assert_eq!(board(&["a1", "b1"]).rotate_anticlockwise(), board(&["h1", "h2"]));
+
+        let random = BitBoard(0x0123456789abcdef);
+        assert_eq!(random.flip_vertical().flip_vertical(), random);
+        assert_eq!(random.mirror_horizontal().mirror_horizontal(), random);
+        assert_eq!(random.flip_diagonal().flip_diagonal(), random);
+        assert_eq!(random.rotate_clockwise().rotate_anticlockwise(), random);
+        assert_eq!(random.flip_diagonal().len(), random.len());
+    }
+
+    #[test]
+    fn display_test() {
+        let drawn = board(&["a1", "h8", "e4"]).to_string();
+        let lines: Vec<&str> = drawn.lines().collect();
+
+        assert_eq!(lines.len(), 9);
+        assert_eq!(lines[0], "8 . . . . . . . 1");
+        assert_eq!(lines[4], "4 . . . . 1 . . .");
+        assert_eq!(lines[7], "1 1 . . . . . . .");
+        assert_eq!(lines[8], "  a b c d e f g h");
+    }
 }
@@ -1,30 +1,565 @@
 use piece_board::PieceBoard;
-use castling::CastlingFlags;
-use piece::{Color, PieceType};
+use castling::{self, CastleType, CastlingFlags, CastlingRooks};
+use piece::{Color, Piece, PieceType};
 use square_position::SquarePosition;
 use std::ops::Index;
 use bit_boards::*;
 use std::convert::TryFrom;
+use std::cmp;
 use std::fmt;
 use std::result::Result;
 use std::str::FromStr;
 use error_types::FromFenError;
+use chess_move::{Move, MoveType};
+use move_gen;
+use outcome::{self, GameOutcome};
+use zobrist::{self, ZOBRIST_KEYS};
 
+/// The FEN of the standard chess starting position.
+pub const STARTING_FEN: &'static str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// The state that cannot be recovered from a move alone, saved by `make_move` so that
+/// `unmake_move` can restore it.
+#[derive(Debug, Copy, Clone)]
+pub struct UndoRecord {
+    captured: Option<Piece>,
+    en_passant: BitBoard,
+    castling_rights: CastlingFlags,
+    halfmove_clock: u32,
+    key: u64,
+}
+
+impl UndoRecord {
+    #[inline]
+    pub fn captured(&self) -> Option<Piece> {
+        self.captured
+    }
+
+    /// The key of the position before the move was made.
+    #[inline]
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+}
+
+#[derive(Clone)]
 pub struct BoardState {
     bit_board: [[BitBoard; 6]; 2],
     bit_occupancy: [BitBoard; 2],
     en_passant: BitBoard,
     piece_board: PieceBoard,
     castling_rights: CastlingFlags,
+    castling_rooks: CastlingRooks,
+    chess960: bool,
     halfmove_clock: u32,
     fullmove_clock: u32,
     active_color: Color,
+    key: u64,
+    pawn_key: u64,
+    undo_stack: Vec<UndoRecord>,
+}
+
+impl BoardState {
+    /// The standard starting position, built directly rather than parsed from `STARTING_FEN`.
+    pub fn starting_position() -> BoardState {
+        let piece_board = PieceBoard::starting_board();
+        let (bit_board, bit_occupancy) = From::from(piece_board);
+
+        let mut board_state = BoardState {
+            bit_board: bit_board,
+            bit_occupancy: bit_occupancy,
+            en_passant: EMPTY,
+            piece_board: piece_board,
+            castling_rights: CastlingFlags::all(),
+            castling_rooks: CastlingRooks::standard(),
+            chess960: false,
+            halfmove_clock: 0,
+            fullmove_clock: 1,
+            active_color: Color::White,
+            key: 0,
+            pawn_key: 0,
+            undo_stack: Vec::new(),
+        };
+        board_state.key = zobrist::compute_key(&board_state);
+        board_state.pawn_key = zobrist::compute_pawn_key(&board_state);
+
+        board_state
+    }
+
+    #[inline]
+    pub fn active_color(&self) -> Color {
+        self.active_color
+    }
+
+    #[inline]
+    pub fn en_passant(&self) -> BitBoard {
+        self.en_passant
+    }
+
+    #[inline]
+    pub fn castling_rights(&self) -> CastlingFlags {
+        self.castling_rights
+    }
+
+    #[inline]
+    pub fn castling_rooks(&self) -> &CastlingRooks {
+        &self.castling_rooks
+    }
+
+    /// Whether castling rights and moves are written in their Chess960 form.
+    #[inline]
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    #[inline]
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
+    }
+
+    #[inline]
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    #[inline]
+    pub fn fullmove_clock(&self) -> u32 {
+        self.fullmove_clock
+    }
+
+    #[inline]
+    pub fn piece_board(&self) -> &PieceBoard {
+        &self.piece_board
+    }
+
+    #[inline]
+    pub fn piece_at(&self, position: SquarePosition) -> Option<Piece> {
+        self.piece_board[position]
+    }
+
+    #[inline]
+    pub fn occupancy(&self) -> BitBoard {
+        self.bit_occupancy[Color::White as usize] | self.bit_occupancy[Color::Black as usize]
+    }
+
+    pub fn pseudo_legal_moves(&self) -> Vec<Move> {
+        move_gen::generate_pseudo_legal_moves(self)
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        move_gen::generate_legal_moves(self)
+    }
+
+    /// The Zobrist key of the position, maintained incrementally by `make_move`.
+    #[inline]
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    /// The Zobrist key of the pawns alone, for caching pawn structure evaluation.
+    #[inline]
+    pub fn pawn_key(&self) -> u64 {
+        self.pawn_key
+    }
+
+    #[inline]
+    pub fn undo_stack(&self) -> &[UndoRecord] {
+        &self.undo_stack
+    }
+
+    /// Counts the earlier occurrences of the current position since the last irreversible move.
+    pub fn repetition_count(&self) -> usize {
+        let reversible_plies = cmp::min(self.halfmove_clock as usize, self.undo_stack.len());
+
+        self.undo_stack
+            .iter()
+            .rev()
+            .take(reversible_plies)
+            .enumerate()
+            .filter(|&(ply, undo)| ply % 2 == 1 && undo.key == self.key)
+            .count()
+    }
+
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        outcome::game_outcome(self)
+    }
+
+    pub fn make_move(&mut self, chess_move: &Move) {
+        let color = self.active_color;
+        let start = *chess_move.start();
+        let end = *chess_move.end();
+        let active = *chess_move.active();
+
+        let captured = match *chess_move.move_type() {
+            MoveType::Capture(captured) |
+            MoveType::CapturePromotion(_, captured) => Some(captured),
+            MoveType::EnPassant => Some(Piece::new(PieceType::Pawn, !color)),
+            _ => None,
+        };
+
+        self.undo_stack.push(UndoRecord {
+            captured: captured,
+            en_passant: self.en_passant,
+            castling_rights: self.castling_rights,
+            halfmove_clock: self.halfmove_clock,
+            key: self.key,
+        });
+
+        self.key ^= ZOBRIST_KEYS.castling(self.castling_rights);
+        if let Some(file) = zobrist::en_passant_file(self) {
+            self.key ^= ZOBRIST_KEYS.en_passant(file);
+        }
+
+        match *chess_move.move_type() {
+            MoveType::Quiet |
+            MoveType::DoublePawnPush => self.move_piece(start, end),
+            MoveType::Capture(_) => {
+                self.remove_piece(end);
+                self.move_piece(start, end);
+            }
+            MoveType::EnPassant => {
+                self.remove_piece(SquarePosition::new(start.rank, end.file));
+                self.move_piece(start, end);
+            }
+            MoveType::Promotion(promotion) => {
+                self.remove_piece(start);
+                self.put_piece(Piece::new(promotion.piece_type(), color), end);
+            }
+            MoveType::CapturePromotion(promotion, _) => {
+                self.remove_piece(end);
+                self.remove_piece(start);
+                self.put_piece(Piece::new(promotion.piece_type(), color), end);
+            }
+            MoveType::Castle(castle_type) => {
+                // The king and rook are lifted before either is placed, as in Chess960 either may
+                // land on the other's starting square.
+                let rook_start = self.castling_rooks.position(color, castle_type);
+                self.remove_piece(start);
+                self.remove_piece(rook_start);
+                self.put_piece(active, end);
+                self.put_piece(Piece::new(PieceType::Rook, color),
+                               SquarePosition::new(start.rank, castle_type.rook_end_file()));
+            }
+        }
+
+        self.en_passant = match *chess_move.move_type() {
+            MoveType::DoublePawnPush => {
+                SquarePosition::new((start.rank + end.rank) / 2, start.file).to_bit_board()
+            }
+            _ => EMPTY,
+        };
+
+        let mut revoked = self.castling_rooks.revoked_by(start) |
+                          self.castling_rooks.revoked_by(end);
+        if active.piece_type() == PieceType::King {
+            revoked |= CastlingFlags::for_color(color);
+        }
+        self.castling_rights.remove(revoked);
+
+        if active.piece_type() == PieceType::Pawn || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        if color == Color::Black {
+            self.fullmove_clock += 1;
+        }
+
+        self.active_color = !color;
+
+        self.key ^= ZOBRIST_KEYS.castling(self.castling_rights) ^ ZOBRIST_KEYS.side();
+        if let Some(file) = zobrist::en_passant_file(self) {
+            self.key ^= ZOBRIST_KEYS.en_passant(file);
+        }
+
+        debug_assert_eq!(self.key, zobrist::compute_key(self));
+        debug_assert_eq!(self.pawn_key, zobrist::compute_pawn_key(self));
+    }
+
+    /// Reverts `chess_move`, which must be the last move passed to `make_move`.
+    pub fn unmake_move(&mut self, chess_move: &Move) {
+        let undo = self.undo_stack
+            .pop()
+            .expect("unmake_move called without a matching make_move");
+        let color = !self.active_color;
+        let start = *chess_move.start();
+        let end = *chess_move.end();
+
+        match *chess_move.move_type() {
+            MoveType::Quiet |
+            MoveType::DoublePawnPush => self.move_piece(end, start),
+            MoveType::Capture(captured) => {
+                self.move_piece(end, start);
+                self.put_piece(captured, end);
+            }
+            MoveType::EnPassant => {
+                self.move_piece(end, start);
+                self.put_piece(Piece::new(PieceType::Pawn, !color),
+                               SquarePosition::new(start.rank, end.file));
+            }
+            MoveType::Promotion(_) => {
+                self.remove_piece(end);
+                self.put_piece(*chess_move.active(), start);
+            }
+            MoveType::CapturePromotion(_, captured) => {
+                self.remove_piece(end);
+                self.put_piece(*chess_move.active(), start);
+                self.put_piece(captured, end);
+            }
+            MoveType::Castle(castle_type) => {
+                let rook_start = self.castling_rooks.position(color, castle_type);
+                self.remove_piece(SquarePosition::new(start.rank, castle_type.rook_end_file()));
+                self.remove_piece(end);
+                self.put_piece(*chess_move.active(), start);
+                self.put_piece(Piece::new(PieceType::Rook, color), rook_start);
+            }
+        }
+
+        self.en_passant = undo.en_passant;
+        self.castling_rights = undo.castling_rights;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.key = undo.key;
+
+        if color == Color::Black {
+            self.fullmove_clock -= 1;
+        }
+
+        self.active_color = color;
+
+        debug_assert_eq!(self.key, zobrist::compute_key(self));
+        debug_assert_eq!(self.pawn_key, zobrist::compute_pawn_key(self));
+    }
+
+    /// Counts the leaf nodes of the legal move tree `depth` plies deep.
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        let mut nodes = 0;
+        for chess_move in moves {
+            self.make_move(&chess_move);
+            nodes += self.perft(depth - 1);
+            self.unmake_move(&chess_move);
+        }
+
+        nodes
+    }
+
+    /// Breaks the perft count down by root move.
+    pub fn divide(&mut self, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+
+        let moves = self.legal_moves();
+        let mut divisions = Vec::with_capacity(moves.len());
+
+        for chess_move in moves {
+            self.make_move(&chess_move);
+            let nodes = self.perft(depth - 1);
+            self.unmake_move(&chess_move);
+
+            divisions.push((chess_move, nodes));
+        }
+
+        divisions
+    }
+
+    fn put_piece(&mut self, piece: Piece, position: SquarePosition) {
+        let square_board = position.to_bit_board();
+
+        self.bit_board[piece.color() as usize][piece.piece_type() as usize] |= square_board;
+        self.bit_occupancy[piece.color() as usize] |= square_board;
+        self.piece_board[position] = Some(piece);
+        self.key ^= ZOBRIST_KEYS.piece(piece, position);
+
+        if piece.piece_type() == PieceType::Pawn {
+            self.pawn_key ^= ZOBRIST_KEYS.piece(piece, position);
+        }
+    }
+
+    fn remove_piece(&mut self, position: SquarePosition) -> Option<Piece> {
+        let removed = self.piece_board[position].take();
+
+        if let Some(piece) = removed {
+            let square_board = position.to_bit_board();
+
+            self.bit_board[piece.color() as usize][piece.piece_type() as usize] &= !square_board;
+            self.bit_occupancy[piece.color() as usize] &= !square_board;
+            self.key ^= ZOBRIST_KEYS.piece(piece, position);
+
+            if piece.piece_type() == PieceType::Pawn {
+                self.pawn_key ^= ZOBRIST_KEYS.piece(piece, position);
+            }
+        }
+
+        removed
+    }
+
+    #[inline]
+    fn move_piece(&mut self, start: SquarePosition, end: SquarePosition) {
+        if let Some(piece) = self.remove_piece(start) {
+            self.put_piece(piece, end);
+        }
+    }
+
+    #[inline]
+    pub fn checkers(&self) -> BitBoard {
+        move_gen::checkers(self)
+    }
+
+    #[inline]
+    pub fn pinned(&self) -> BitBoard {
+        move_gen::pinned(self)
+    }
+
+    /// Every piece of either color attacking `position`, with sliders blocked by `occupancy`
+    /// rather than the board's own occupancy so that x-rays can be found by removing pieces.
+    #[inline]
+    pub fn attackers_to(&self, position: SquarePosition, occupancy: BitBoard) -> BitBoard {
+        move_gen::attackers_to(self, position, occupancy)
+    }
+
+    #[inline]
+    pub fn is_attacked(&self, position: SquarePosition, by_color: Color) -> bool {
+        move_gen::square_attacked(self, position, by_color, self.occupancy())
+    }
+
+    /// Whether the side to move is in check.
+    #[inline]
+    pub fn in_check(&self) -> bool {
+        !self.checkers().is_empty()
+    }
+
+    /// The same position seen from the other side: the ranks are flipped, every piece changes
+    /// color and the other side is to move, so it must evaluate the same as the original.
+    /// Castling rights and the en passant square follow the pieces. The move history is not
+    /// carried over.
+    pub fn flip_colors(&self) -> BoardState {
+        let mut castling_rights = CastlingFlags::empty();
+        let mut castling_rooks = self.castling_rooks;
+
+        for &color in [Color::White, Color::Black].iter() {
+            for &castle_type in [CastleType::Kingside, CastleType::Queenside].iter() {
+                if self.castling_rights.contains(CastlingFlags::for_castle(!color, castle_type)) {
+                    castling_rights |= CastlingFlags::for_castle(color, castle_type);
+                }
+
+                castling_rooks.set_file(color,
+                                        castle_type,
+                                        self.castling_rooks.file(!color, castle_type));
+            }
+        }
+
+        self.transformed(|position| SquarePosition::new(7 - position.rank, position.file),
+                         true,
+                         self.en_passant.flip_vertical(),
+                         castling_rights,
+                         castling_rooks)
+    }
+
+    /// The position with the files mirrored, a to h. The king and rooks change wings, so each
+    /// kingside right becomes a queenside right with its rook on the mirrored file, and the
+    /// result is a Chess960 position. The move history is not carried over.
+    pub fn mirror_files(&self) -> BoardState {
+        let mut castling_rights = CastlingFlags::empty();
+        let mut castling_rooks = self.castling_rooks;
+
+        for &color in [Color::White, Color::Black].iter() {
+            for &(from, to) in [(CastleType::Kingside, CastleType::Queenside),
+                                (CastleType::Queenside, CastleType::Kingside)]
+                .iter() {
+                if self.castling_rights.contains(CastlingFlags::for_castle(color, from)) {
+                    castling_rights |= CastlingFlags::for_castle(color, to);
+                }
+
+                castling_rooks.set_file(color, to, 7 - self.castling_rooks.file(color, from));
+            }
+        }
+
+        self.transformed(|position| SquarePosition::new(position.rank, 7 - position.file),
+                         false,
+                         self.en_passant.mirror_horizontal(),
+                         castling_rights,
+                         castling_rooks)
+    }
+
+    /// The board turned half way round with the colors swapped, the same as flipping the colors
+    /// then mirroring the files.
+    pub fn rotate_180(&self) -> BoardState {
+        self.flip_colors().mirror_files()
+    }
+
+    fn transformed<F>(&self,
+                      map_position: F,
+                      swap_colors: bool,
+                      en_passant: BitBoard,
+                      castling_rights: CastlingFlags,
+                      castling_rooks: CastlingRooks)
+                      -> BoardState
+        where F: Fn(SquarePosition) -> SquarePosition
+    {
+        let mut piece_board = PieceBoard::empty_board();
+
+        for (position, piece) in &self.piece_board {
+            let color = if swap_colors { !piece.color() } else { piece.color() };
+            piece_board[map_position(position)] = Some(Piece::new(piece.piece_type(), color));
+        }
+
+        let (bit_board, bit_occupancy) = From::from(piece_board);
+        let chess960 = self.chess960 || !castling_rooks.is_standard() ||
+                       !castling::kings_on_standard_squares(castling_rights, &piece_board);
+
+        let mut board_state = BoardState {
+            bit_board: bit_board,
+            bit_occupancy: bit_occupancy,
+            en_passant: en_passant,
+            piece_board: piece_board,
+            castling_rights: castling_rights,
+            castling_rooks: castling_rooks,
+            chess960: chess960,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_clock: self.fullmove_clock,
+            active_color: if swap_colors { !self.active_color } else { self.active_color },
+            key: 0,
+            pawn_key: 0,
+            undo_stack: Vec::new(),
+        };
+        board_state.key = zobrist::compute_key(&board_state);
+        board_state.pawn_key = zobrist::compute_pawn_key(&board_state);
+
+        board_state
+    }
+}
+
+/// Controls how much checking `BoardState::from_fen` does beyond the FEN syntax.
+#[derive(Debug, Copy, Hash, Clone, PartialEq, Eq)]
+pub enum FenValidation {
+    /// Reject positions that could not arise in a legal game.
+    Strict,
+    /// Accept any syntactically correct FEN, for setting up test and puzzle positions.
+    Lenient,
 }
 
 impl FromStr for BoardState {
     type Err = FromFenError;
 
     fn from_str(fen_string: &str) -> Result<BoardState, FromFenError> {
+        BoardState::from_fen(fen_string, FenValidation::Strict)
+    }
+}
+
+impl BoardState {
+    pub fn from_fen(fen_string: &str,
+                    validation: FenValidation)
+                    -> Result<BoardState, FromFenError> {
         let components = fen_string.split_whitespace().collect::<Vec<&str>>();
         if components.len() == 6 {
             let piece_board: PieceBoard = components[0].parse()?;
@@ -34,31 +569,138 @@ impl FromStr for BoardState {
 
             let active_color: Color = components[1].parse()?;
 
-            let castling_rights: CastlingFlags = components[2].parse()?;
+            let (castling_rights, castling_rooks) =
+                castling::parse_castling_field(components[2], &piece_board)?;
+            let chess960 = !castling_rooks.is_standard() ||
+                           !castling::kings_on_standard_squares(castling_rights, &piece_board);
 
             let en_passant_position: BitBoard = match components[3].parse::<SquarePosition>() {
                 Ok(position) => BitBoard::from(position),
-                Err(_) => 0,
+                Err(_) => EMPTY,
             };
 
             let halfmove_clock: u32 = components[4].parse()?;
 
             let fullmove_clock: u32 = components[5].parse()?;
 
-            Ok(BoardState {
+            let mut board_state = BoardState {
                 bit_board: bit_board,
                 bit_occupancy: bit_occupancy,
                 en_passant: en_passant_position,
                 piece_board: piece_board,
                 castling_rights: castling_rights,
+                castling_rooks: castling_rooks,
+                chess960: chess960,
                 halfmove_clock: halfmove_clock,
                 fullmove_clock: fullmove_clock,
                 active_color: active_color,
-            })
+                key: 0,
+                pawn_key: 0,
+                undo_stack: Vec::new(),
+            };
+            board_state.key = zobrist::compute_key(&board_state);
+            board_state.pawn_key = zobrist::compute_pawn_key(&board_state);
+
+            if validation == FenValidation::Strict {
+                board_state.validate()?;
+            }
+
+            Ok(board_state)
         } else {
             Err(FromFenError::IncorrectNumberOfFields(components.len()))
         }
     }
+
+    /// Checks that the position could have been reached in a legal game.
+    pub fn validate(&self) -> Result<(), FromFenError> {
+        for &color in [Color::White, Color::Black].iter() {
+            match self[(color, PieceType::King)].len() {
+                0 => return Err(FromFenError::MissingKing(color)),
+                1 => {}
+                count => return Err(FromFenError::MultipleKings(color, count as usize)),
+            }
+        }
+
+        let back_rank_pawns = (self[(Color::White, PieceType::Pawn)] |
+                               self[(Color::Black, PieceType::Pawn)]) &
+                              (RANK_BOARDS[0] | RANK_BOARDS[7]);
+        if let Some(square_index) = bit_scan_forward(back_rank_pawns) {
+            return Err(FromFenError::PawnOnBackRank(SquarePosition::try_from(square_index)
+                .unwrap()));
+        }
+
+        let inactive_king = bit_scan_forward(self[(!self.active_color, PieceType::King)]).unwrap();
+        if move_gen::square_attacked(self,
+                                     SquarePosition::try_from(inactive_king).unwrap(),
+                                     self.active_color,
+                                     self.occupancy()) {
+            return Err(FromFenError::InactiveSideInCheck);
+        }
+
+        let invalid_rights = self.castling_rights - self.supported_castling_rights();
+        if !invalid_rights.is_empty() {
+            return Err(FromFenError::InvalidCastlingRights(invalid_rights));
+        }
+
+        if let Some(square_index) = bit_scan_forward(self.en_passant) {
+            let position = SquarePosition::try_from(square_index).unwrap();
+
+            if !self.en_passant_supported(position) {
+                return Err(FromFenError::InvalidEnPassant(position));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The castling rights that the placement of the kings and rooks still allows: the king
+    /// on its back rank with the castling rook on the correct side of it.
+    fn supported_castling_rights(&self) -> CastlingFlags {
+        let mut supported = CastlingFlags::empty();
+
+        for &color in [Color::White, Color::Black].iter() {
+            let king = bit_scan_forward(self[(color, PieceType::King)])
+                .map(|square_index| SquarePosition::try_from(square_index).unwrap());
+            let king_file = match king {
+                Some(position) if position.rank == castling::back_rank(color) => position.file,
+                _ => continue,
+            };
+
+            for &castle_type in [CastleType::Kingside, CastleType::Queenside].iter() {
+                let rook = self.castling_rooks.position(color, castle_type);
+                let correct_side = match castle_type {
+                    CastleType::Kingside => rook.file > king_file,
+                    CastleType::Queenside => rook.file < king_file,
+                };
+
+                if correct_side && self[(color, PieceType::Rook)].contains(rook) {
+                    supported |= CastlingFlags::for_castle(color, castle_type);
+                }
+            }
+        }
+
+        supported
+    }
+
+    /// An en passant square is only possible directly behind a pawn of the side that just
+    /// moved, with both the square and the pawn's starting square left empty.
+    fn en_passant_supported(&self, position: SquarePosition) -> bool {
+        let (target_rank, pawn_rank, start_rank) = match self.active_color {
+            Color::White => (5, 4, 6),
+            Color::Black => (2, 3, 1),
+        };
+
+        if position.rank != target_rank {
+            return false;
+        }
+
+        let pawn = SquarePosition::new(pawn_rank, position.file).to_bit_board();
+        let emptied = position.to_bit_board() |
+                      SquarePosition::new(start_rank, position.file).to_bit_board();
+
+        !(self[(!self.active_color, PieceType::Pawn)] & pawn).is_empty() &&
+        (self.occupancy() & emptied).is_empty()
+    }
 }
 
 impl fmt::Display for BoardState {
@@ -68,11 +710,17 @@ impl fmt::Display for BoardState {
                 SquarePosition::try_from(square_index).unwrap().to_string()
             });
 
+        let castling_field = if self.chess960 {
+            castling::format_shredder_castling_field(self.castling_rights, &self.castling_rooks)
+        } else {
+            self.castling_rights.to_string()
+        };
+
         write!(f,
                "{} {} {} {} {} {}",
                self.piece_board,
                self.active_color,
-               self.castling_rights,
+               castling_field,
                en_passant_position,
                self.halfmove_clock,
                self.fullmove_clock)
@@ -100,40 +748,194 @@ impl Index<Color> for BoardState {
 #[cfg(test)]
 mod test {
     use bit_boards::BitBoard;
-    use board_state::BoardState;
-    use piece_board::PieceBoard;
+    use board_state::{BoardState, FenValidation, STARTING_FEN};
     use std::convert::From;
-    use castling::CastlingFlags;
+    use castling::CastleType;
     use piece::Color;
-
-    fn starting_board_state() -> BoardState {
-        let starting_piece_board = PieceBoard::starting_board();
-        let (bit_board, bit_occupancy) = From::from(starting_piece_board);
-
-        BoardState {
-            bit_board: bit_board,
-            bit_occupancy: bit_occupancy,
-            piece_board: starting_piece_board,
-            en_passant: 0 as BitBoard,
-            castling_rights: CastlingFlags::all(),
-            halfmove_clock: 0,
-            fullmove_clock: 1,
-            active_color: Color::White,
-        }
-    }
+    use test_helpers::{board, find_move, square};
 
     #[test]
     fn starting_to_string_test() {
         assert_eq!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
-                   starting_board_state().to_string());
+                   BoardState::starting_position().to_string());
     }
 
     #[test]
     fn starting_from_string_test() {
-        assert_eq!(starting_board_state().to_string(),
+        assert_eq!(BoardState::starting_position().to_string(),
                    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
                        .parse::<BoardState>()
                        .unwrap()
                        .to_string());
     }
+
+    #[test]
+    fn make_move_sequence_test() {
+        let mut board = BoardState::starting_position();
+        let moves = [("e2", "e4", "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+                     ("c7", "c5", "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2"),
+                     ("g1", "f3", "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2")];
+
+        for &(start, end, expected) in moves.iter() {
+            let chess_move = find_move(&board, start, end);
+            board.make_move(&chess_move);
+            assert_eq!(board.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn make_unmake_round_trip_test() {
+        let fen_strings = ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                           "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+                           "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"];
+
+        for fen_string in fen_strings.iter() {
+            let mut board: BoardState = fen_string.parse().unwrap();
+
+            for chess_move in board.legal_moves() {
+                board.make_move(&chess_move);
+                assert_eq!(board.undo_stack().len(), 1);
+                board.unmake_move(&chess_move);
+
+                let (bit_board, bit_occupancy): ([[BitBoard; 6]; 2], [BitBoard; 2]) =
+                    From::from(*board.piece_board());
+
+                assert_eq!(board.to_string(), *fen_string);
+                assert_eq!(bit_board, board.bit_board);
+                assert_eq!(bit_occupancy, board.bit_occupancy);
+            }
+        }
+    }
+
+    #[test]
+    fn castling_rights_revoked_test() {
+        let mut board: BoardState = "r2qk2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".parse().unwrap();
+
+        let rook_move = find_move(&board, "a1", "a8");
+        board.make_move(&rook_move);
+        assert_eq!(board.to_string(), "R2qk2r/8/8/8/8/8/8/4K2R b Kk - 0 1");
+
+        let castle = find_move(&board, "e8", "g8");
+        board.make_move(&castle);
+        assert_eq!(board.to_string(), "R2q1rk1/8/8/8/8/8/8/4K2R w K - 1 2");
+    }
+
+    #[test]
+    fn strict_fen_validation_test() {
+        let invalid = ["rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1",
+                       "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKKNR w kq - 0 1",
+                       "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNP w kq - 0 1",
+                       "rnbqkbnr/ppppp1pp/8/5p1Q/4P3/8/PPPP1PPP/RNB1KBNR w KQkq - 0 1",
+                       "rnbqkbnr/pppppppp/8/8/8/4K3/PPPPPPPP/RNBQ1BNR w KQkq - 0 1",
+                       "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1",
+                       "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e6 0 1"];
+
+        let errors: Vec<String> = invalid.iter()
+            .map(|fen_string| match fen_string.parse::<BoardState>() {
+                Err(err) => format!("{:?}", err),
+                Ok(_) => String::new(),
+            })
+            .collect();
+
+        assert_eq!(errors,
+                   vec!["MissingKing(Black)",
+                        "MultipleKings(White, 2)",
+                        "PawnOnBackRank(SquarePosition { rank: 0, file: 7 })",
+                        "InactiveSideInCheck",
+                        "InvalidCastlingRights(WHITE_QUEENSIDE | WHITE_KINGSIDE)",
+                        "InvalidCastlingRights(WHITE_KINGSIDE)",
+                        "InvalidEnPassant(SquarePosition { rank: 5, file: 4 })"]);
+    }
+
+    #[test]
+    fn lenient_fen_validation_test() {
+        let fen_strings = ["rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNP w Qkq - 0 1",
+                           "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1",
+                           "4k3/8/8/8/8/8/8/4K3 w KQkq - 0 1"];
+
+        for fen_string in fen_strings.iter() {
+            assert!(fen_string.parse::<BoardState>().is_err());
+            assert_eq!(BoardState::from_fen(fen_string, FenValidation::Lenient)
+                           .unwrap()
+                           .to_string(),
+                       *fen_string);
+        }
+    }
+
+    #[test]
+    fn attack_queries_test() {
+        let checked: BoardState = "4k3/8/8/3p4/4P3/8/1B6/R3K2r w - - 0 1".parse().unwrap();
+        let occupancy = checked.occupancy();
+
+        assert_eq!(checked.attackers_to(square("d5"), occupancy), board(&["e4"]));
+        assert_eq!(checked.attackers_to(square("e4"), occupancy), board(&["d5"]));
+        assert_eq!(checked.attackers_to(square("d1"), occupancy), board(&["a1", "e1"]));
+        assert_eq!(checked.attackers_to(square("d1"), occupancy & !board(&["e1"])),
+                   board(&["a1", "h1"]));
+
+        assert!(checked.is_attacked(square("d4"), Color::White));
+        assert!(!checked.is_attacked(square("d4"), Color::Black));
+        assert!(checked.is_attacked(square("f1"), Color::Black));
+        assert!(checked.in_check());
+
+        let quiet: BoardState = "4k3/8/8/3p4/4P3/8/1B5r/R3K3 w - - 0 1".parse().unwrap();
+        assert!(!quiet.in_check());
+    }
+
+    #[test]
+    fn flip_colors_test() {
+        let board: BoardState = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"
+            .parse()
+            .unwrap();
+        let flipped = board.flip_colors();
+
+        assert_eq!(flipped.to_string(),
+                   "rnbqkbnr/pppp1ppp/8/8/3PpP2/8/PPP1P1PP/RNBQKBNR b KQkq f3 0 3");
+        assert!(flipped.validate().is_ok());
+        assert_eq!(flipped.flip_colors().to_string(), board.to_string());
+        assert_eq!(flipped.flip_colors().key(), board.key());
+
+        let castling: BoardState = "r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1".parse().unwrap();
+        assert_eq!(castling.flip_colors().to_string(), "4k2r/8/8/8/8/8/8/R3K3 b Qk - 0 1");
+    }
+
+    #[test]
+    fn mirror_and_rotate_test() {
+        let board: BoardState = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"
+            .parse()
+            .unwrap();
+
+        assert_eq!(board.mirror_files().to_string(),
+                   "rnbkqbnr/pp1p1ppp/8/2pPp3/8/8/PPP1PPPP/RNBKQBNR w HAha c6 0 3");
+        assert_eq!(board.rotate_180().to_string(),
+                   "rnbkqbnr/ppp1pppp/8/8/2PpP3/8/PP1P1PPP/RNBKQBNR b HAha c3 0 3");
+        assert_eq!(board.rotate_180().to_string(),
+                   board.mirror_files().flip_colors().to_string());
+    }
+
+    #[test]
+    fn transformed_perft_test() {
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut board: BoardState = kiwipete.parse().unwrap();
+        let expected = board.perft(3);
+        assert_eq!(board.flip_colors().perft(3), expected);
+
+        let mut without_castling: BoardState =
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w - - 0 1".parse().unwrap();
+        let expected = without_castling.perft(3);
+        assert_eq!(without_castling.mirror_files().perft(3), expected);
+        assert_eq!(without_castling.rotate_180().perft(3), expected);
+
+        // Chess960 castling puts the king on the c or g file, which is not the mirror of
+        // standard castling, so the rights can only be compared while castling is out of reach.
+        let mut starting: BoardState = STARTING_FEN.parse().unwrap();
+        let mut rotated = starting.rotate_180();
+        assert_eq!(rotated.castling_rights(), starting.castling_rights());
+        assert_eq!(rotated.castling_rooks().file(Color::White, CastleType::Kingside), 7);
+        assert_eq!(rotated.castling_rooks().file(Color::Black, CastleType::Queenside), 0);
+        assert_eq!(rotated.rotate_180().castling_rooks(), starting.castling_rooks());
+
+        let expected = starting.perft(3);
+        assert_eq!(rotated.perft(3), expected);
+    }
 }
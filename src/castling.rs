@@ -1,6 +1,9 @@
 use std::fmt;
 use std::str::FromStr;
 use error_types::FromStrError;
+use piece::{Color, Piece, PieceType};
+use piece_board::PieceBoard;
+use square_position::SquarePosition;
 
 bitflags! {
     pub flags CastlingFlags: u8 {
@@ -11,9 +14,207 @@ bitflags! {
     }
 }
 
+#[derive(Debug, Copy, Hash, Clone, PartialEq, Eq)]
 pub enum CastleType {
-    Kingside,
-    Queenside,
+    Kingside = 0,
+    Queenside = 1,
+}
+
+impl CastlingFlags {
+    /// The single right to castle to `castle_type` for `color`.
+    #[inline]
+    pub fn for_castle(color: Color, castle_type: CastleType) -> CastlingFlags {
+        match (color, castle_type) {
+            (Color::White, CastleType::Kingside) => WHITE_KINGSIDE,
+            (Color::White, CastleType::Queenside) => WHITE_QUEENSIDE,
+            (Color::Black, CastleType::Kingside) => BLACK_KINGSIDE,
+            (Color::Black, CastleType::Queenside) => BLACK_QUEENSIDE,
+        }
+    }
+
+    #[inline]
+    pub fn for_color(color: Color) -> CastlingFlags {
+        CastlingFlags::for_castle(color, CastleType::Kingside) |
+        CastlingFlags::for_castle(color, CastleType::Queenside)
+    }
+}
+
+impl CastleType {
+    /// The file the king ends up on, which is the same in standard chess and Chess960.
+    #[inline]
+    pub fn king_end_file(&self) -> usize {
+        match *self {
+            CastleType::Kingside => 6,
+            CastleType::Queenside => 2,
+        }
+    }
+
+    /// The file the rook ends up on, which is the same in standard chess and Chess960.
+    #[inline]
+    pub fn rook_end_file(&self) -> usize {
+        match *self {
+            CastleType::Kingside => 5,
+            CastleType::Queenside => 3,
+        }
+    }
+}
+
+/// The starting files of the rooks each side may castle with. Standard chess always uses the a
+/// and h files, while in Chess960 they can be anywhere on the back rank either side of the king.
+#[derive(Debug, Copy, Hash, Clone, PartialEq, Eq)]
+pub struct CastlingRooks {
+    files: [[usize; 2]; 2],
+}
+
+impl CastlingRooks {
+    #[inline]
+    pub fn standard() -> CastlingRooks {
+        CastlingRooks { files: [[7, 0]; 2] }
+    }
+
+    #[inline]
+    pub fn file(&self, color: Color, castle_type: CastleType) -> usize {
+        self.files[color as usize][castle_type as usize]
+    }
+
+    #[inline]
+    pub fn position(&self, color: Color, castle_type: CastleType) -> SquarePosition {
+        SquarePosition::new(back_rank(color), self.file(color, castle_type))
+    }
+
+    #[inline]
+    pub fn set_file(&mut self, color: Color, castle_type: CastleType, file: usize) {
+        self.files[color as usize][castle_type as usize] = file;
+    }
+
+    #[inline]
+    pub fn is_standard(&self) -> bool {
+        *self == CastlingRooks::standard()
+    }
+
+    /// The rights that are lost once a piece moves from or to the given square, not counting
+    /// the king moving.
+    pub fn revoked_by(&self, position: SquarePosition) -> CastlingFlags {
+        let mut revoked = CastlingFlags::empty();
+
+        for &color in [Color::White, Color::Black].iter() {
+            for &castle_type in [CastleType::Kingside, CastleType::Queenside].iter() {
+                if self.position(color, castle_type) == position {
+                    revoked |= CastlingFlags::for_castle(color, castle_type);
+                }
+            }
+        }
+
+        revoked
+    }
+}
+
+#[inline]
+pub fn back_rank(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 7,
+    }
+}
+
+fn king_file(piece_board: &PieceBoard, color: Color) -> Option<usize> {
+    let rank = back_rank(color);
+
+    (0..8).find(|&file| {
+        piece_board[SquarePosition::new(rank, file)] == Some(Piece::new(PieceType::King, color))
+    })
+}
+
+/// Whether every side with castling rights still has its king on the e-file.
+pub fn kings_on_standard_squares(rights: CastlingFlags, piece_board: &PieceBoard) -> bool {
+    [Color::White, Color::Black].iter().all(|&color| {
+        !rights.intersects(CastlingFlags::for_color(color)) ||
+        king_file(piece_board, color) == Some(4)
+    })
+}
+
+/// Parses a FEN castling field in the standard, Shredder-FEN (`HAha`) or X-FEN (`KQkq`, with file
+/// letters for inner rooks) form. `K` and `Q` refer to the outermost rook on that side of the
+/// king. A right without a rook to castle with is kept, on the a or h file for `K` and `Q`, so
+/// that `BoardState::validate` can report it; move generation never castles with a missing rook.
+pub fn parse_castling_field(castling_string: &str,
+                            piece_board: &PieceBoard)
+                            -> Result<(CastlingFlags, CastlingRooks), FromStrError> {
+    if castling_string.len() == 0 || castling_string.len() > 4 {
+        return Err(FromStrError::InvalidInputLength("castling", 4, castling_string.len()));
+    }
+
+    let mut rights = CastlingFlags::empty();
+    let mut rooks = CastlingRooks::standard();
+
+    if castling_string == "-" {
+        return Ok((rights, rooks));
+    }
+
+    for castling_char in castling_string.chars() {
+        let color = if castling_char.is_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let rank = back_rank(color);
+        let rook = Some(Piece::new(PieceType::Rook, color));
+        let king = king_file(piece_board, color).unwrap_or(4);
+
+        let (castle_type, file) = match castling_char {
+            'K' | 'k' => {
+                let file = (king + 1..8)
+                    .rev()
+                    .find(|&file| piece_board[SquarePosition::new(rank, file)] == rook);
+                (CastleType::Kingside, file.unwrap_or(7))
+            }
+            'Q' | 'q' => {
+                let file = (0..king)
+                    .find(|&file| piece_board[SquarePosition::new(rank, file)] == rook);
+                (CastleType::Queenside, file.unwrap_or(0))
+            }
+            'A'...'H' | 'a'...'h' => {
+                let file = castling_char.to_lowercase().next().unwrap() as usize - 'a' as usize;
+                if file == king {
+                    return Err(FromStrError::MalformedInput("castling"));
+                } else if file > king {
+                    (CastleType::Kingside, file)
+                } else {
+                    (CastleType::Queenside, file)
+                }
+            }
+            _ => return Err(FromStrError::MalformedInput("castling")),
+        };
+
+        rights |= CastlingFlags::for_castle(color, castle_type);
+        rooks.set_file(color, castle_type, file);
+    }
+
+    Ok((rights, rooks))
+}
+
+/// Formats the castling field with the file letter of each castling rook, as Shredder-FEN does.
+pub fn format_shredder_castling_field(rights: CastlingFlags, rooks: &CastlingRooks) -> String {
+    if rights.is_empty() {
+        return "-".to_owned();
+    }
+
+    let mut result = String::new();
+
+    for &color in [Color::White, Color::Black].iter() {
+        for &castle_type in [CastleType::Kingside, CastleType::Queenside].iter() {
+            if rights.contains(CastlingFlags::for_castle(color, castle_type)) {
+                let file_char = (b'a' + rooks.file(color, castle_type) as u8) as char;
+
+                match color {
+                    Color::White => result.extend(file_char.to_uppercase()),
+                    Color::Black => result.push(file_char),
+                }
+            }
+        }
+    }
+
+    result
 }
 
 impl fmt::Display for CastlingFlags {
@@ -48,10 +249,6 @@ impl FromStr for CastlingFlags {
     type Err = FromStrError;
     fn from_str(castling_string: &str) -> Result<Self, Self::Err> {
         if castling_string.len() == 0 || castling_string.len() > 4 {
-            println!("Equals zero: {:?}", castling_string.len() == 0);
-            println!("Greater than four {:?}", castling_string.len() > 4);
-            println!("Both? {:?}",
-                     castling_string.len() == 0 || castling_string.len() > 4);
             Err(FromStrError::InvalidInputLength("castling", 4, castling_string.len()))
         } else {
             Ok(castling_string.chars()
@@ -77,7 +274,11 @@ impl FromStr for CastlingFlags {
 
 #[cfg(test)]
 mod test {
-    use castling::{BLACK_KINGSIDE, BLACK_QUEENSIDE, CastlingFlags, WHITE_KINGSIDE, WHITE_QUEENSIDE};
+    use castling::{BLACK_KINGSIDE, BLACK_QUEENSIDE, CastleType, CastlingFlags, CastlingRooks,
+                   WHITE_KINGSIDE, WHITE_QUEENSIDE, format_shredder_castling_field,
+                   parse_castling_field};
+    use piece::Color;
+    use piece_board::PieceBoard;
 
     #[test]
     fn str_to_flag_test() {
@@ -129,4 +330,50 @@ mod test {
         }
     }
 
+    #[test]
+    fn shredder_field_test() {
+        let piece_board = PieceBoard::starting_board();
+        let (rights, rooks) = parse_castling_field("HAha", &piece_board).unwrap();
+
+        assert_eq!(rights, CastlingFlags::all());
+        assert!(rooks.is_standard());
+        assert_eq!(format_shredder_castling_field(rights, &rooks), "HAha");
+    }
+
+    #[test]
+    fn chess960_field_test() {
+        let piece_board: PieceBoard = "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB"
+            .parse()
+            .unwrap();
+
+        let (rights, rooks) = parse_castling_field("GE", &piece_board).unwrap();
+        assert_eq!(rights, WHITE_KINGSIDE | WHITE_QUEENSIDE);
+        assert_eq!(rooks.file(Color::White, CastleType::Kingside), 6);
+        assert_eq!(rooks.file(Color::White, CastleType::Queenside), 4);
+
+        let (x_fen_rights, x_fen_rooks) = parse_castling_field("KQq", &piece_board).unwrap();
+        assert_eq!(x_fen_rights, WHITE_KINGSIDE | WHITE_QUEENSIDE | BLACK_QUEENSIDE);
+        assert_eq!(x_fen_rooks.file(Color::White, CastleType::Kingside), 6);
+        assert_eq!(x_fen_rooks.file(Color::Black, CastleType::Queenside), 4);
+        assert_eq!(format_shredder_castling_field(x_fen_rights, &x_fen_rooks), "GEe");
+
+        let (_, inner_rooks) = parse_castling_field("Ef", &piece_board).unwrap();
+        assert_eq!(inner_rooks.file(Color::White, CastleType::Queenside), 4);
+        assert!(inner_rooks != CastlingRooks::standard());
+
+        assert!(parse_castling_field("Fk", &piece_board).is_err());
+        assert!(parse_castling_field("F", &piece_board).is_err());
+
+        let (_, missing_rook) = parse_castling_field("D", &piece_board).unwrap();
+        assert_eq!(missing_rook.file(Color::White, CastleType::Queenside), 3);
+    }
+
+    #[test]
+    fn missing_rook_field_test() {
+        let piece_board: PieceBoard = "r3k3/8/8/8/8/8/8/4K2R".parse().unwrap();
+
+        let (rights, rooks) = parse_castling_field("KQkq", &piece_board).unwrap();
+        assert_eq!(rights, CastlingFlags::all());
+        assert_eq!(rooks, CastlingRooks::standard());
+    }
 }
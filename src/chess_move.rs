@@ -2,6 +2,7 @@ use piece::{Piece, PromotionType};
 use square_position::SquarePosition;
 use castling::CastleType;
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Move {
     start_position: SquarePosition,
     end_position: SquarePosition,
@@ -45,10 +46,12 @@ impl Move {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum MoveType {
     Quiet,
     Capture(Piece),
     DoublePawnPush,
+    EnPassant,
     Promotion(PromotionType),
     Castle(CastleType),
     CapturePromotion(PromotionType, Piece),
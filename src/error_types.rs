@@ -2,6 +2,11 @@ use std::error::Error;
 use std::convert::From;
 use std::num::ParseIntError;
 use std::fmt;
+use std::io;
+
+use castling::CastlingFlags;
+use piece::Color;
+use square_position::SquarePosition;
 
 #[derive(Debug)]
 pub enum FromStrError {
@@ -44,6 +49,12 @@ pub enum FromFenError {
     IncorrectNumberOfFields(usize),
     MalformedStringField(FromStrError),
     IntFieldParseError(ParseIntError),
+    MissingKing(Color),
+    MultipleKings(Color, usize),
+    PawnOnBackRank(SquarePosition),
+    InactiveSideInCheck,
+    InvalidCastlingRights(CastlingFlags),
+    InvalidEnPassant(SquarePosition),
 }
 
 impl From<ParseIntError> for FromFenError {
@@ -64,14 +75,24 @@ impl Error for FromFenError {
             FromFenError::IncorrectNumberOfFields(_) => "fen str had an incorrect numer of fields",
             FromFenError::MalformedStringField(ref err) => err.description(),
             FromFenError::IntFieldParseError(ref err) => err.description(),
+            FromFenError::MissingKing(_) => "fen str was missing a king",
+            FromFenError::MultipleKings(_, _) => "fen str had more than one king for a side",
+            FromFenError::PawnOnBackRank(_) => "fen str had a pawn on the first or eighth rank",
+            FromFenError::InactiveSideInCheck => "fen str had the side not to move in check",
+            FromFenError::InvalidCastlingRights(_) => {
+                "fen str had castling rights without a matching king and rook"
+            }
+            FromFenError::InvalidEnPassant(_) => {
+                "fen str had an en passant square no pawn could have passed"
+            }
         }
     }
 
     fn cause(&self) -> Option<&Error> {
         match *self {
-            FromFenError::IncorrectNumberOfFields(_) => None,
             FromFenError::MalformedStringField(ref err) => Some(err as &Error),
             FromFenError::IntFieldParseError(ref err) => Some(err as &Error),
+            _ => None,
         }
     }
 }
@@ -84,6 +105,142 @@ impl fmt::Display for FromFenError {
             }
             FromFenError::MalformedStringField(ref err) => write!(f, "{}", err),
             FromFenError::IntFieldParseError(ref err) => write!(f, "{}", err),
+            FromFenError::MissingKing(color) => write!(f, "No king found for \"{}\"", color),
+            FromFenError::MultipleKings(color, count) => {
+                write!(f, "Found {} kings for \"{}\", expected 1", count, color)
+            }
+            FromFenError::PawnOnBackRank(position) => {
+                write!(f, "Pawn found on back rank at {}", position)
+            }
+            FromFenError::InactiveSideInCheck => write!(f, "The side not to move is in check"),
+            FromFenError::InvalidCastlingRights(rights) => {
+                write!(f,
+                       "Castling rights \"{}\" do not match the king and rook placement",
+                       rights)
+            }
+            FromFenError::InvalidEnPassant(position) => {
+                write!(f, "En passant square {} could not have been passed", position)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SanError {
+    MalformedInput(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+
+impl Error for SanError {
+    fn description(&self) -> &str {
+        match *self {
+            SanError::MalformedInput(_) => "san str was malformed",
+            SanError::IllegalMove(_) => "san str did not match any legal move",
+            SanError::AmbiguousMove(_) => "san str matched more than one legal move",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        None
+    }
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SanError::MalformedInput(ref san) => write!(f, "Malformed move \"{}\"", san),
+            SanError::IllegalMove(ref san) => write!(f, "Illegal move \"{}\"", san),
+            SanError::AmbiguousMove(ref san) => write!(f, "Ambiguous move \"{}\"", san),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum UciMoveError {
+    MalformedInput(String),
+    IllegalMove(String),
+}
+
+impl Error for UciMoveError {
+    fn description(&self) -> &str {
+        match *self {
+            UciMoveError::MalformedInput(_) => "uci move str was malformed",
+            UciMoveError::IllegalMove(_) => "uci move str did not match any legal move",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        None
+    }
+}
+
+impl fmt::Display for UciMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UciMoveError::MalformedInput(ref uci) => write!(f, "Malformed move \"{}\"", uci),
+            UciMoveError::IllegalMove(ref uci) => write!(f, "Illegal move \"{}\"", uci),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PgnError {
+    Io(io::Error),
+    MalformedTag(usize),
+    UnterminatedComment(usize),
+    MalformedMovetext(usize, &'static str),
+    InvalidFen(FromFenError),
+    IllegalMove(usize, SanError),
+}
+
+impl From<io::Error> for PgnError {
+    fn from(err: io::Error) -> PgnError {
+        PgnError::Io(err)
+    }
+}
+
+impl From<FromFenError> for PgnError {
+    fn from(err: FromFenError) -> PgnError {
+        PgnError::InvalidFen(err)
+    }
+}
+
+impl Error for PgnError {
+    fn description(&self) -> &str {
+        match *self {
+            PgnError::Io(ref err) => err.description(),
+            PgnError::MalformedTag(_) => "pgn tag pair was malformed",
+            PgnError::UnterminatedComment(_) => "pgn comment was not terminated",
+            PgnError::MalformedMovetext(_, _) => "pgn movetext was malformed",
+            PgnError::InvalidFen(ref err) => err.description(),
+            PgnError::IllegalMove(_, ref err) => err.description(),
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            PgnError::Io(ref err) => Some(err as &Error),
+            PgnError::InvalidFen(ref err) => Some(err as &Error),
+            PgnError::IllegalMove(_, ref err) => Some(err as &Error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PgnError::Io(ref err) => write!(f, "{}", err),
+            PgnError::MalformedTag(line) => write!(f, "Malformed tag pair on line {}", line),
+            PgnError::UnterminatedComment(line) => {
+                write!(f, "Comment starting on line {} was not terminated", line)
+            }
+            PgnError::MalformedMovetext(line, reason) => {
+                write!(f, "Malformed movetext on line {}: {}", line, reason)
+            }
+            PgnError::InvalidFen(ref err) => write!(f, "Invalid FEN tag: {}", err),
+            PgnError::IllegalMove(line, ref err) => write!(f, "{} on line {}", err, line),
         }
     }
 }
@@ -0,0 +1,215 @@
+//! King safety: enemy pieces attacking the squares around the king, and the pawns and open files
+//! in front of it.
+
+use std::cmp;
+use std::convert::TryFrom;
+
+use bit_boards::{BitBoard, EMPTY, FILE_BOARDS, bit_scan_forward, bit_scan_reverse};
+use board_state::BoardState;
+use evaluation::Score;
+use evaluation::pawns::{FORWARD_RANKS, relative_rank};
+use move_gen::{BitboardGenerator, KING_ATTACK_BOARD, attacks, pawn_direction};
+use piece::{Color, Piece, PieceType};
+use square_position::SquarePosition;
+
+const ATTACKING_TYPES: [PieceType; 4] =
+    [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight];
+
+/// The weight of each attack into the king zone, indexed by the attacker's `PieceType`.
+const ATTACK_WEIGHTS: [i32; 6] = [0, 5, 2, 2, 3, 0];
+
+/// The percentage of the attack weight that counts, indexed by the number of attackers. A single
+/// attacker is rarely dangerous on its own.
+const ATTACKER_SCALE: [i32; 8] = [0, 0, 50, 75, 88, 94, 97, 99];
+
+const MAX_DANGER: i32 = 600;
+
+/// The bonus for the nearest pawn in front of the king on each of the king's and the adjacent
+/// files, indexed by how many ranks it stands in front of the king. Index 0 is used when there
+/// is no pawn, and pawns further away than the table count as missing.
+const SHIELD_BONUS: [i32; 4] = [-20, 25, 15, 5];
+
+/// The penalty for the nearest enemy pawn advancing on each of the king's and the adjacent files,
+/// indexed by how many ranks in front of the king it stands.
+const STORM_PENALTY: [i32; 5] = [0, -10, -30, -15, -5];
+
+const SEMI_OPEN_FILE_PENALTY: Score = Score {
+    midgame: -12,
+    endgame: 0,
+};
+const OPEN_FILE_PENALTY: Score = Score {
+    midgame: -25,
+    endgame: -5,
+};
+
+lazy_static! {
+    /// The squares around each king including its own, together with the three squares one
+    /// rank further forward, indexed by color then square.
+    pub static ref KING_ZONES: [[BitBoard; 64]; 2] = {
+        let mut king_zones = [[EMPTY; 64]; 2];
+
+        for &color in [Color::White, Color::Black].iter() {
+            for square_index in 0..64 {
+                let square = SquarePosition::try_from(square_index).unwrap();
+                let ring = KING_ATTACK_BOARD.get_attacks(square, EMPTY) |
+                           BitBoard(1 << square_index);
+
+                king_zones[color as usize][square_index] = ring | ring.shift(pawn_direction(color));
+            }
+        }
+
+        king_zones
+    };
+}
+
+#[inline]
+fn king_position(board: &BoardState, color: Color) -> Option<SquarePosition> {
+    board[(color, PieceType::King)].lsb()
+}
+
+#[inline]
+pub fn king_zone(color: Color, position: SquarePosition) -> BitBoard {
+    KING_ZONES[color as usize][position.to_square_index()]
+}
+
+/// The weighted count of attacks by the other side's pieces into the zone around `color`'s king,
+/// scaled by how many pieces take part in the attack.
+pub fn king_attack_units(board: &BoardState, color: Color) -> i32 {
+    let zone = match king_position(board, color) {
+        Some(king) => king_zone(color, king),
+        None => return 0,
+    };
+    let occupancy = board.occupancy();
+    let mut attackers = 0;
+    let mut weight = 0;
+
+    for &piece_type in ATTACKING_TYPES.iter() {
+        let piece = Piece::new(piece_type, !color);
+
+        for position in board[(!color, piece_type)] {
+            let zone_attacks = attacks(piece, position, occupancy) & zone;
+
+            if !zone_attacks.is_empty() {
+                attackers += 1;
+                weight += ATTACK_WEIGHTS[piece_type as usize] * zone_attacks.len() as i32;
+            }
+        }
+    }
+
+    weight * ATTACKER_SCALE[cmp::min(attackers, ATTACKER_SCALE.len() - 1)] / 100
+}
+
+/// The nearest pawn of `pawns` in front of `color`'s king on `file`, as a number of ranks ahead
+/// of the king.
+fn nearest_ahead(color: Color,
+                 king: SquarePosition,
+                 file: usize,
+                 pawns: BitBoard)
+                 -> Option<usize> {
+    let ahead = pawns & FILE_BOARDS[file] & FORWARD_RANKS[color as usize][king.rank];
+    let nearest = match color {
+        Color::White => bit_scan_forward(ahead),
+        Color::Black => bit_scan_reverse(ahead),
+    };
+
+    nearest.map(|square_index| {
+        let pawn = SquarePosition::try_from(square_index).unwrap();
+        relative_rank(color, pawn) - relative_rank(color, king)
+    })
+}
+
+/// The pawn shield in front of `color`'s king, the enemy pawns storming it, and the open and
+/// semi-open files on and beside the king's file.
+pub fn pawn_shelter(board: &BoardState, color: Color) -> Score {
+    let king = match king_position(board, color) {
+        Some(king) => king,
+        None => return Score::default(),
+    };
+    let own_pawns = board[(color, PieceType::Pawn)];
+    let enemy_pawns = board[(!color, PieceType::Pawn)];
+    let first_file = if king.file > 0 { king.file - 1 } else { 0 };
+    let last_file = cmp::min(king.file + 1, 7);
+    let mut shelter = 0;
+    let mut score = Score::default();
+
+    for file in first_file..(last_file + 1) {
+        shelter += match nearest_ahead(color, king, file, own_pawns) {
+            Some(distance) if distance < SHIELD_BONUS.len() => SHIELD_BONUS[distance],
+            _ => SHIELD_BONUS[0],
+        };
+
+        if let Some(distance) = nearest_ahead(color, king, file, enemy_pawns) {
+            if distance < STORM_PENALTY.len() {
+                shelter += STORM_PENALTY[distance];
+            }
+        }
+
+        if (own_pawns & FILE_BOARDS[file]).is_empty() {
+            score += if (enemy_pawns & FILE_BOARDS[file]).is_empty() {
+                OPEN_FILE_PENALTY
+            } else {
+                SEMI_OPEN_FILE_PENALTY
+            };
+        }
+    }
+
+    score + Score::new(shelter, 0)
+}
+
+/// The safety of `color`'s king. The danger from attacks grows with the square of the attack
+/// units, and matters far less once the queens and most pieces are off.
+pub fn king_safety(board: &BoardState, color: Color) -> Score {
+    let units = king_attack_units(board, color);
+    let danger = cmp::min(units * units / 2, MAX_DANGER);
+
+    pawn_shelter(board, color) - Score::new(danger, danger / 8)
+}
+
+#[cfg(test)]
+mod test {
+    use board_state::BoardState;
+    use evaluation::king_safety::{king_attack_units, king_safety, king_zone, pawn_shelter};
+    use piece::Color;
+    use test_helpers::{board, square};
+
+    #[test]
+    fn king_zone_test() {
+        assert_eq!(king_zone(Color::White, square("g1")),
+                   board(&["f1", "g1", "h1", "f2", "g2", "h2", "f3", "g3", "h3"]));
+        assert_eq!(king_zone(Color::Black, square("a8")),
+                   board(&["a8", "b8", "a7", "b7", "a6", "b6"]));
+        assert_eq!(king_zone(Color::White, square("e4")).len(), 12);
+    }
+
+    #[test]
+    fn king_attack_units_test() {
+        let quiet: BoardState =
+            "r1bq1rk1/pppp1ppp/2n2n2/2b1p3/2B1P3/2N2N2/PPPP1PPP/R1BQ1RK1 w - - 0 1"
+                .parse()
+                .unwrap();
+        let attacked: BoardState =
+            "r1b2rk1/pppp1ppp/2n5/2b1p1NQ/2B1P3/8/PPPP1PPP/RNB2RK1 w - - 0 1".parse().unwrap();
+
+        assert_eq!(king_attack_units(&attacked, Color::White), 0);
+        assert!(king_attack_units(&attacked, Color::Black) >
+                king_attack_units(&quiet, Color::Black));
+        assert!(king_safety(&attacked, Color::Black).midgame <
+                king_safety(&quiet, Color::Black).midgame);
+    }
+
+    #[test]
+    fn pawn_shelter_test() {
+        let intact: BoardState = "6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1".parse().unwrap();
+        let advanced: BoardState = "6k1/5ppp/8/8/8/6PP/5P2/6K1 w - - 0 1".parse().unwrap();
+        let open: BoardState = "6k1/5ppp/8/8/8/8/5P1P/6K1 w - - 0 1".parse().unwrap();
+        let stormed: BoardState = "6k1/5p2/8/8/8/6pp/5PPP/6K1 w - - 0 1".parse().unwrap();
+
+        let intact_shelter = pawn_shelter(&intact, Color::White);
+
+        assert_eq!(intact_shelter, pawn_shelter(&intact, Color::Black));
+        assert!(pawn_shelter(&advanced, Color::White).midgame < intact_shelter.midgame);
+        assert!(pawn_shelter(&open, Color::White).midgame <
+                pawn_shelter(&advanced, Color::White).midgame);
+        assert!(pawn_shelter(&stormed, Color::White).midgame < intact_shelter.midgame);
+    }
+}
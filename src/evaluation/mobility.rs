@@ -0,0 +1,346 @@
+//! Piece mobility, counted over the squares a piece can go to without landing on one of its own
+//! pieces or a square guarded by an enemy pawn, and the bonuses and penalties for where the
+//! pieces stand.
+
+use bit_boards::{BitBoard, FILE_BOARDS, RANK_BOARDS};
+use board_state::BoardState;
+use castling::CastlingFlags;
+use evaluation::Score;
+use evaluation::pawns::{ADJACENT_FILES, PASSED_PAWN_MASKS, relative_rank};
+use move_gen::{PAWN_ATTACK_BOARD, attacks, pawn_direction};
+use piece::{Color, Piece, PieceType};
+use square_position::{EAST, SquarePosition, WEST};
+
+const MOBILE_TYPES: [PieceType; 4] =
+    [PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen];
+
+/// Indexed by the number of safe squares the knight can move to.
+#[cfg_attr(rustfmt, rustfmt_skip)]
+const KNIGHT_MOBILITY: [Score; 9] = [Score { midgame: -62, endgame: -81 },
+                                     Score { midgame: -53, endgame: -56 },
+                                     Score { midgame: -12, endgame: -30 },
+                                     Score { midgame: -4, endgame: -14 },
+                                     Score { midgame: 3, endgame: 8 },
+                                     Score { midgame: 13, endgame: 15 },
+                                     Score { midgame: 22, endgame: 23 },
+                                     Score { midgame: 28, endgame: 27 },
+                                     Score { midgame: 33, endgame: 33 }];
+
+/// Indexed by the number of safe squares the bishop can move to.
+#[cfg_attr(rustfmt, rustfmt_skip)]
+const BISHOP_MOBILITY: [Score; 14] = [Score { midgame: -48, endgame: -59 },
+                                      Score { midgame: -20, endgame: -23 },
+                                      Score { midgame: 16, endgame: -3 },
+                                      Score { midgame: 26, endgame: 13 },
+                                      Score { midgame: 38, endgame: 24 },
+                                      Score { midgame: 51, endgame: 42 },
+                                      Score { midgame: 55, endgame: 54 },
+                                      Score { midgame: 63, endgame: 57 },
+                                      Score { midgame: 63, endgame: 65 },
+                                      Score { midgame: 68, endgame: 73 },
+                                      Score { midgame: 81, endgame: 78 },
+                                      Score { midgame: 81, endgame: 86 },
+                                      Score { midgame: 91, endgame: 88 },
+                                      Score { midgame: 98, endgame: 97 }];
+
+/// Indexed by the number of safe squares the rook can move to.
+#[cfg_attr(rustfmt, rustfmt_skip)]
+const ROOK_MOBILITY: [Score; 15] = [Score { midgame: -58, endgame: -76 },
+                                    Score { midgame: -27, endgame: -18 },
+                                    Score { midgame: -15, endgame: 28 },
+                                    Score { midgame: -10, endgame: 55 },
+                                    Score { midgame: -5, endgame: 69 },
+                                    Score { midgame: -2, endgame: 82 },
+                                    Score { midgame: 9, endgame: 112 },
+                                    Score { midgame: 16, endgame: 118 },
+                                    Score { midgame: 30, endgame: 132 },
+                                    Score { midgame: 29, endgame: 142 },
+                                    Score { midgame: 32, endgame: 155 },
+                                    Score { midgame: 38, endgame: 165 },
+                                    Score { midgame: 46, endgame: 166 },
+                                    Score { midgame: 48, endgame: 169 },
+                                    Score { midgame: 58, endgame: 171 }];
+
+/// Indexed by the number of safe squares the queen can move to.
+#[cfg_attr(rustfmt, rustfmt_skip)]
+const QUEEN_MOBILITY: [Score; 28] = [Score { midgame: -39, endgame: -36 },
+                                     Score { midgame: -21, endgame: -15 },
+                                     Score { midgame: 3, endgame: 8 },
+                                     Score { midgame: 3, endgame: 18 },
+                                     Score { midgame: 14, endgame: 34 },
+                                     Score { midgame: 22, endgame: 54 },
+                                     Score { midgame: 28, endgame: 61 },
+                                     Score { midgame: 41, endgame: 73 },
+                                     Score { midgame: 43, endgame: 79 },
+                                     Score { midgame: 48, endgame: 92 },
+                                     Score { midgame: 56, endgame: 94 },
+                                     Score { midgame: 60, endgame: 104 },
+                                     Score { midgame: 60, endgame: 113 },
+                                     Score { midgame: 66, endgame: 120 },
+                                     Score { midgame: 67, endgame: 123 },
+                                     Score { midgame: 70, endgame: 126 },
+                                     Score { midgame: 71, endgame: 133 },
+                                     Score { midgame: 73, endgame: 136 },
+                                     Score { midgame: 79, endgame: 140 },
+                                     Score { midgame: 88, endgame: 143 },
+                                     Score { midgame: 88, endgame: 148 },
+                                     Score { midgame: 99, endgame: 166 },
+                                     Score { midgame: 102, endgame: 170 },
+                                     Score { midgame: 102, endgame: 175 },
+                                     Score { midgame: 106, endgame: 184 },
+                                     Score { midgame: 109, endgame: 191 },
+                                     Score { midgame: 113, endgame: 206 },
+                                     Score { midgame: 116, endgame: 212 }];
+
+/// The mobility tables indexed by `PieceType`. Kings and pawns have no mobility score.
+const MOBILITY_TABLES: [&'static [Score]; 6] =
+    [&[], &QUEEN_MOBILITY, &BISHOP_MOBILITY, &KNIGHT_MOBILITY, &ROOK_MOBILITY, &[]];
+
+const BISHOP_PAIR_BONUS: Score = Score {
+    midgame: 30,
+    endgame: 50,
+};
+const ROOK_OPEN_FILE_BONUS: Score = Score {
+    midgame: 45,
+    endgame: 20,
+};
+const ROOK_SEMI_OPEN_FILE_BONUS: Score = Score {
+    midgame: 20,
+    endgame: 7,
+};
+const ROOK_ON_SEVENTH_BONUS: Score = Score {
+    midgame: 20,
+    endgame: 40,
+};
+const KNIGHT_OUTPOST_BONUS: Score = Score {
+    midgame: 30,
+    endgame: 20,
+};
+const TRAPPED_ROOK_PENALTY: Score = Score {
+    midgame: -50,
+    endgame: -10,
+};
+const TRAPPED_BISHOP_PENALTY: Score = Score {
+    midgame: -100,
+    endgame: -120,
+};
+
+/// The highest number of safe squares at which a rook boxed in by its own king counts as
+/// trapped.
+const TRAPPED_ROOK_MOBILITY: usize = 3;
+
+/// The squares, as (rank, file) from the owner's side, where a bishop is trapped by an enemy
+/// pawn on the paired square.
+const TRAPPED_BISHOP_SQUARES: [((usize, usize), (usize, usize)); 4] =
+    [((6, 0), (5, 1)), ((6, 7), (5, 6)), ((5, 0), (4, 1)), ((5, 7), (4, 6))];
+
+/// The position of the square at `rank` counted from `color`'s side and `file`.
+#[inline]
+fn relative_square(color: Color, rank: usize, file: usize) -> SquarePosition {
+    match color {
+        Color::White => SquarePosition::new(rank, file),
+        Color::Black => SquarePosition::new(7 - rank, file),
+    }
+}
+
+/// Every square attacked by one of `color`'s pawns.
+pub fn pawn_attacks(board: &BoardState, color: Color) -> BitBoard {
+    let pawns = board[(color, PieceType::Pawn)];
+    let forward = pawn_direction(color);
+
+    pawns.shift(forward + EAST) | pawns.shift(forward + WEST)
+}
+
+/// The squares that count towards the mobility of `color`'s pieces: those not occupied by its
+/// own pieces and not attacked by an enemy pawn.
+#[inline]
+pub fn mobility_area(board: &BoardState, color: Color) -> BitBoard {
+    !board[color] & !pawn_attacks(board, !color)
+}
+
+/// The number of squares in `area` that `piece` on `position` can move to.
+#[inline]
+fn safe_squares(board: &BoardState,
+                piece: Piece,
+                position: SquarePosition,
+                area: BitBoard)
+                -> usize {
+    (attacks(piece, position, board.occupancy()) & area).len()
+}
+
+/// The mobility of `color`'s knights, bishops, rooks and queens.
+pub fn mobility(board: &BoardState, color: Color) -> Score {
+    let area = mobility_area(board, color);
+    let mut score = Score::default();
+
+    for &piece_type in MOBILE_TYPES.iter() {
+        let piece = Piece::new(piece_type, color);
+        let table = MOBILITY_TABLES[piece_type as usize];
+
+        for position in board[(color, piece_type)] {
+            score += table[safe_squares(board, piece, position, area)];
+        }
+    }
+
+    score
+}
+
+/// Whether `color`'s knight on `position` stands on the fourth to sixth rank, defended by one of
+/// its own pawns, where no enemy pawn can ever attack it.
+pub fn is_knight_outpost(board: &BoardState, color: Color, position: SquarePosition) -> bool {
+    let rank = relative_rank(color, position);
+    let square_index = position.to_square_index();
+    let defenders = board[(color, PieceType::Pawn)] &
+                    PAWN_ATTACK_BOARD.get_attacks(!color, position);
+    let attack_span = PASSED_PAWN_MASKS[color as usize][square_index] &
+                      ADJACENT_FILES[square_index];
+
+    rank >= 3 && rank <= 5 && !defenders.is_empty() &&
+    (board[(!color, PieceType::Pawn)] & attack_span).is_empty()
+}
+
+/// Whether `color`'s rook on `position` is shut in on the back rank by its own king, which can
+/// no longer castle to free it.
+pub fn is_trapped_rook(board: &BoardState, color: Color, position: SquarePosition) -> bool {
+    let king = match board[(color, PieceType::King)].lsb() {
+        Some(king) => king,
+        None => return false,
+    };
+
+    if relative_rank(color, position) != 0 || king.rank != position.rank ||
+       board.castling_rights().intersects(CastlingFlags::for_color(color)) {
+        return false;
+    }
+
+    let boxed_in = (king.file < 4 && position.file < king.file) ||
+                   (king.file >= 4 && position.file > king.file);
+    let rook = Piece::new(PieceType::Rook, color);
+
+    boxed_in &&
+    safe_squares(board, rook, position, mobility_area(board, color)) <= TRAPPED_ROOK_MOBILITY
+}
+
+/// Whether `color`'s bishop on `position` is stuck on the edge behind an enemy pawn, as after
+/// taking a poisoned pawn on a7 or h7.
+pub fn is_trapped_bishop(board: &BoardState, color: Color, position: SquarePosition) -> bool {
+    TRAPPED_BISHOP_SQUARES.iter().any(|&((rank, file), (pawn_rank, pawn_file))| {
+        position == relative_square(color, rank, file) &&
+        board[(!color, PieceType::Pawn)].contains(relative_square(color, pawn_rank, pawn_file))
+    })
+}
+
+/// The bonuses and penalties for where `color`'s pieces stand: the bishop pair, rooks on open
+/// files and the seventh rank, knights on outposts, and trapped rooks and bishops.
+pub fn piece_activity(board: &BoardState, color: Color) -> Score {
+    let own_pawns = board[(color, PieceType::Pawn)];
+    let enemy_pawns = board[(!color, PieceType::Pawn)];
+    let mut score = Score::default();
+
+    if board[(color, PieceType::Bishop)].len() >= 2 {
+        score += BISHOP_PAIR_BONUS;
+    }
+
+    for position in board[(color, PieceType::Bishop)] {
+        if is_trapped_bishop(board, color, position) {
+            score += TRAPPED_BISHOP_PENALTY;
+        }
+    }
+
+    for position in board[(color, PieceType::Knight)] {
+        if is_knight_outpost(board, color, position) {
+            score += KNIGHT_OUTPOST_BONUS;
+        }
+    }
+
+    let enemy_king_rank = board[(!color, PieceType::King)]
+        .lsb()
+        .map(|king| relative_rank(color, king));
+    let seventh_rank = RANK_BOARDS[relative_square(color, 6, 0).rank];
+    let enemy_pawns_on_seventh = !(enemy_pawns & seventh_rank).is_empty();
+
+    for position in board[(color, PieceType::Rook)] {
+        let file = FILE_BOARDS[position.file];
+
+        if (own_pawns & file).is_empty() {
+            score += if (enemy_pawns & file).is_empty() {
+                ROOK_OPEN_FILE_BONUS
+            } else {
+                ROOK_SEMI_OPEN_FILE_BONUS
+            };
+        }
+
+        if relative_rank(color, position) == 6 &&
+           (enemy_king_rank == Some(7) || enemy_pawns_on_seventh) {
+            score += ROOK_ON_SEVENTH_BONUS;
+        }
+
+        if is_trapped_rook(board, color, position) {
+            score += TRAPPED_ROOK_PENALTY;
+        }
+    }
+
+    score
+}
+
+#[cfg(test)]
+mod test {
+    use board_state::BoardState;
+    use evaluation::mobility::{is_knight_outpost, is_trapped_bishop, is_trapped_rook, mobility,
+                               mobility_area, piece_activity};
+    use piece::Color;
+    use test_helpers::square;
+
+    #[test]
+    fn mobility_test() {
+        let central: BoardState = "4k3/8/8/8/4N3/8/8/4K3 w - - 0 1".parse().unwrap();
+        let cornered: BoardState = "4k3/8/8/8/8/8/8/N3K3 w - - 0 1".parse().unwrap();
+        let guarded: BoardState = "4k3/2p5/8/8/4N3/8/8/4K3 w - - 0 1".parse().unwrap();
+
+        assert!(mobility(&central, Color::White).midgame >
+                mobility(&cornered, Color::White).midgame);
+        assert!(mobility(&guarded, Color::White).midgame <
+                mobility(&central, Color::White).midgame);
+        assert!(!mobility_area(&guarded, Color::White).contains(square("d6")));
+        assert!(!mobility_area(&guarded, Color::White).contains(square("e1")));
+        assert!(mobility_area(&guarded, Color::White).contains(square("c6")));
+    }
+
+    #[test]
+    fn knight_outpost_test() {
+        let board: BoardState = "4k3/2p5/8/3N1N2/4P3/8/8/4K3 w - - 0 1".parse().unwrap();
+
+        assert!(is_knight_outpost(&board, Color::White, square("f5")));
+        assert!(!is_knight_outpost(&board, Color::White, square("d5")));
+    }
+
+    #[test]
+    fn trapped_pieces_test() {
+        let rook: BoardState = "4k3/8/8/8/8/8/5PPP/5KNR w - - 0 1".parse().unwrap();
+        let castling: BoardState = "4k3/8/8/8/8/8/5PPP/4K1NR w K - 0 1".parse().unwrap();
+        let bishop: BoardState = "4k3/B7/1p6/8/8/8/8/4K3 w - - 0 1".parse().unwrap();
+
+        assert!(is_trapped_rook(&rook, Color::White, square("h1")));
+        assert!(!is_trapped_rook(&castling, Color::White, square("h1")));
+        assert!(is_trapped_bishop(&bishop, Color::White, square("a7")));
+        assert!(is_trapped_bishop(&bishop.flip_colors(), Color::Black, square("a2")));
+    }
+
+    #[test]
+    fn piece_activity_test() {
+        let closed: BoardState = "4k3/p7/8/8/8/8/P7/R3K3 w - - 0 1".parse().unwrap();
+        let semi_open: BoardState = "4k3/p7/8/8/8/8/1P6/R3K3 w - - 0 1".parse().unwrap();
+        let open: BoardState = "4k3/1p6/8/8/8/8/1P6/R3K3 w - - 0 1".parse().unwrap();
+        let seventh: BoardState = "4k3/R7/8/8/8/8/1P6/4K3 w - - 0 1".parse().unwrap();
+
+        let closed_score = piece_activity(&closed, Color::White).midgame;
+        let semi_open_score = piece_activity(&semi_open, Color::White).midgame;
+        let open_score = piece_activity(&open, Color::White).midgame;
+
+        assert!(closed_score < semi_open_score);
+        assert!(semi_open_score < open_score);
+        assert!(piece_activity(&seventh, Color::White).midgame > open_score);
+
+        let pair: BoardState = "4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1".parse().unwrap();
+        assert!(piece_activity(&pair, Color::White).midgame > 0);
+    }
+}
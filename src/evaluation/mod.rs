@@ -0,0 +1,208 @@
+//! Static evaluation of a position. Every term is scored separately for the midgame and the
+//! endgame, and the two are blended by how much non-pawn material is left on the board.
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use board_state::BoardState;
+use evaluation::pawns::{DEFAULT_PAWN_TABLE_SIZE, PawnEntry, PawnHashTable};
+use piece::Color;
+
+pub mod king_safety;
+pub mod material;
+pub mod mobility;
+pub mod pawns;
+
+/// A pair of midgame and endgame values, in centipawns.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Score {
+    pub midgame: i32,
+    pub endgame: i32,
+}
+
+impl Score {
+    #[inline]
+    pub fn new(midgame: i32, endgame: i32) -> Score {
+        Score {
+            midgame: midgame,
+            endgame: endgame,
+        }
+    }
+
+    /// Blends the two values by `phase`, which runs from `MAX_PHASE` with all pieces on the
+    /// board, giving the midgame value, down to 0 with only kings and pawns, giving the endgame
+    /// value.
+    #[inline]
+    pub fn taper(&self, phase: i32) -> i32 {
+        (self.midgame * phase + self.endgame * (material::MAX_PHASE - phase)) /
+        material::MAX_PHASE
+    }
+}
+
+impl Add for Score {
+    type Output = Score;
+
+    #[inline]
+    fn add(self, other: Score) -> Score {
+        Score::new(self.midgame + other.midgame, self.endgame + other.endgame)
+    }
+}
+
+impl Sub for Score {
+    type Output = Score;
+
+    #[inline]
+    fn sub(self, other: Score) -> Score {
+        Score::new(self.midgame - other.midgame, self.endgame - other.endgame)
+    }
+}
+
+impl Neg for Score {
+    type Output = Score;
+
+    #[inline]
+    fn neg(self) -> Score {
+        Score::new(-self.midgame, -self.endgame)
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Score;
+
+    #[inline]
+    fn mul(self, other: i32) -> Score {
+        Score::new(self.midgame * other, self.endgame * other)
+    }
+}
+
+impl AddAssign for Score {
+    #[inline]
+    fn add_assign(&mut self, other: Score) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Score {
+    #[inline]
+    fn sub_assign(&mut self, other: Score) {
+        *self = *self - other;
+    }
+}
+
+/// Every term for `color` apart from the pawn structure, which is scored for both sides at once.
+fn color_score(board: &BoardState, color: Color) -> Score {
+    material::material(board, color) + king_safety::king_safety(board, color) +
+    mobility::mobility(board, color) + mobility::piece_activity(board, color)
+}
+
+/// The evaluation of `board` for white before tapering, taking the pawn structure from `pawns`.
+pub fn white_score(board: &BoardState, pawns: &PawnEntry) -> Score {
+    color_score(board, Color::White) - color_score(board, Color::Black) + pawns.score()
+}
+
+fn side_to_move_score(board: &BoardState, pawns: &PawnEntry) -> i32 {
+    let score = white_score(board, pawns).taper(material::game_phase(board));
+
+    match board.active_color() {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+/// The evaluation of `board` in centipawns from the point of view of the side to move. The pawn
+/// structure is evaluated from scratch, so searches should use an `Evaluator` instead.
+pub fn evaluate(board: &BoardState) -> i32 {
+    side_to_move_score(board, &pawns::evaluate_pawns(board))
+}
+
+/// Evaluates positions with the caches that make repeated evaluation during a search cheap.
+pub struct Evaluator {
+    pawn_table: PawnHashTable,
+}
+
+impl Evaluator {
+    pub fn new() -> Evaluator {
+        Evaluator::with_pawn_table_size(DEFAULT_PAWN_TABLE_SIZE)
+    }
+
+    pub fn with_pawn_table_size(size: usize) -> Evaluator {
+        Evaluator { pawn_table: PawnHashTable::new(size) }
+    }
+
+    /// The same as `evaluate`, taking the pawn structure from the pawn hash table when it can.
+    pub fn evaluate(&mut self, board: &BoardState) -> i32 {
+        let pawns = self.pawn_table.probe(board);
+        side_to_move_score(board, &pawns)
+    }
+
+    /// Empties the caches, for starting a new game.
+    pub fn clear(&mut self) {
+        self.pawn_table.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use board_state::{BoardState, STARTING_FEN};
+    use evaluation::{Evaluator, Score, evaluate};
+    use evaluation::material::MAX_PHASE;
+
+    #[test]
+    fn taper_test() {
+        let score = Score::new(100, -20);
+
+        assert_eq!(score.taper(MAX_PHASE), 100);
+        assert_eq!(score.taper(0), -20);
+        assert_eq!(score.taper(MAX_PHASE / 2), 40);
+        assert_eq!(-score * 2 + Score::new(10, 10), Score::new(-190, 50));
+    }
+
+    #[test]
+    fn starting_position_test() {
+        let board: BoardState = STARTING_FEN.parse().unwrap();
+
+        assert_eq!(evaluate(&board), 0);
+        assert_eq!(evaluate(&board.flip_colors()), 0);
+    }
+
+    #[test]
+    fn side_to_move_test() {
+        let white: BoardState = "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+            .parse()
+            .unwrap();
+        let black: BoardState = "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"
+            .parse()
+            .unwrap();
+
+        assert!(evaluate(&white) > 800);
+        assert_eq!(evaluate(&black), -evaluate(&white));
+    }
+
+    #[test]
+    fn flip_symmetry_test() {
+        let fen_strings = ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                           "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+                           "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                           "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"];
+
+        for fen_string in fen_strings.iter() {
+            let board: BoardState = fen_string.parse().unwrap();
+
+            assert_eq!(evaluate(&board), evaluate(&board.flip_colors()));
+        }
+    }
+
+    #[test]
+    fn evaluator_test() {
+        let mut evaluator = Evaluator::with_pawn_table_size(64);
+        let mut board: BoardState =
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+
+        for chess_move in board.legal_moves() {
+            board.make_move(&chess_move);
+            assert_eq!(evaluator.evaluate(&board), evaluate(&board));
+            board.unmake_move(&chess_move);
+        }
+
+        assert_eq!(evaluator.evaluate(&board), evaluate(&board));
+    }
+}
@@ -0,0 +1,362 @@
+//! Pawn structure: passed, isolated, doubled, backward and connected pawns. The pawns change far
+//! less often than the rest of the position, so the result is cached in a `PawnHashTable` keyed
+//! by the board's pawn key.
+
+use bit_boards::{BitBoard, EMPTY, FILE_BOARDS, RANK_BOARDS};
+use board_state::BoardState;
+use evaluation::Score;
+use move_gen::{PAWN_ATTACK_BOARD, pawn_direction};
+use piece::{Color, PieceType};
+use square_position::SquarePosition;
+
+/// The number of entries in the pawn hash table of a new `Evaluator`.
+pub const DEFAULT_PAWN_TABLE_SIZE: usize = 1 << 14;
+
+const ISOLATED_PENALTY: Score = Score {
+    midgame: -5,
+    endgame: -15,
+};
+const DOUBLED_PENALTY: Score = Score {
+    midgame: -11,
+    endgame: -56,
+};
+const BACKWARD_PENALTY: Score = Score {
+    midgame: -9,
+    endgame: -24,
+};
+
+/// Indexed by the rank of the pawn counted from its own side.
+#[cfg_attr(rustfmt, rustfmt_skip)]
+const PASSED_BONUS: [Score; 8] = [Score { midgame: 0, endgame: 0 },
+                                  Score { midgame: 5, endgame: 10 },
+                                  Score { midgame: 10, endgame: 17 },
+                                  Score { midgame: 15, endgame: 25 },
+                                  Score { midgame: 30, endgame: 50 },
+                                  Score { midgame: 55, endgame: 100 },
+                                  Score { midgame: 90, endgame: 160 },
+                                  Score { midgame: 0, endgame: 0 }];
+
+/// Indexed by the rank of the pawn counted from its own side.
+#[cfg_attr(rustfmt, rustfmt_skip)]
+const CONNECTED_BONUS: [Score; 8] = [Score { midgame: 0, endgame: 0 },
+                                     Score { midgame: 7, endgame: 0 },
+                                     Score { midgame: 8, endgame: 4 },
+                                     Score { midgame: 12, endgame: 6 },
+                                     Score { midgame: 29, endgame: 18 },
+                                     Score { midgame: 48, endgame: 40 },
+                                     Score { midgame: 86, endgame: 80 },
+                                     Score { midgame: 0, endgame: 0 }];
+
+lazy_static! {
+    /// The ranks strictly in front of each rank, indexed by color then rank.
+    pub static ref FORWARD_RANKS: [[BitBoard; 8]; 2] = {
+        let mut forward_ranks = [[EMPTY; 8]; 2];
+
+        for rank in 0..8 {
+            for ahead in (rank + 1)..8 {
+                forward_ranks[Color::White as usize][rank] |= RANK_BOARDS[ahead];
+            }
+            for behind in 0..rank {
+                forward_ranks[Color::Black as usize][rank] |= RANK_BOARDS[behind];
+            }
+        }
+
+        forward_ranks
+    };
+    /// The whole files either side of each square's file, indexed by square.
+    pub static ref ADJACENT_FILES: [BitBoard; 64] = {
+        let mut adjacent_files = [EMPTY; 64];
+
+        for square_index in 0..64 {
+            let file = square_index % 8;
+
+            if file > 0 {
+                adjacent_files[square_index] |= FILE_BOARDS[file - 1];
+            }
+            if file < 7 {
+                adjacent_files[square_index] |= FILE_BOARDS[file + 1];
+            }
+        }
+
+        adjacent_files
+    };
+    /// The squares in front of each square on its own file, indexed by color then square.
+    pub static ref FORWARD_FILES: [[BitBoard; 64]; 2] = {
+        let mut forward_files = [[EMPTY; 64]; 2];
+
+        for color in 0..2 {
+            for square_index in 0..64 {
+                forward_files[color][square_index] = FILE_BOARDS[square_index % 8] &
+                                                     FORWARD_RANKS[color][square_index / 8];
+            }
+        }
+
+        forward_files
+    };
+    /// The squares in front of each square on its own and the adjacent files, which must be
+    /// free of enemy pawns for a pawn there to be passed. Indexed by color then square.
+    pub static ref PASSED_PAWN_MASKS: [[BitBoard; 64]; 2] = {
+        let mut passed_pawn_masks = [[EMPTY; 64]; 2];
+
+        for color in 0..2 {
+            for square_index in 0..64 {
+                passed_pawn_masks[color][square_index] =
+                    (FILE_BOARDS[square_index % 8] | ADJACENT_FILES[square_index]) &
+                    FORWARD_RANKS[color][square_index / 8];
+            }
+        }
+
+        passed_pawn_masks
+    };
+}
+
+/// The rank of `position` counted from `color`'s side of the board.
+#[inline]
+pub fn relative_rank(color: Color, position: SquarePosition) -> usize {
+    match color {
+        Color::White => position.rank,
+        Color::Black => 7 - position.rank,
+    }
+}
+
+/// Whether no enemy pawn can stop or capture `color`'s pawn on `position` on its way to
+/// promotion. Only the front pawn of doubled pawns counts as passed.
+pub fn is_passed(board: &BoardState, color: Color, position: SquarePosition) -> bool {
+    let square_index = position.to_square_index();
+
+    (board[(!color, PieceType::Pawn)] & PASSED_PAWN_MASKS[color as usize][square_index])
+        .is_empty() && !is_doubled(board, color, position)
+}
+
+/// Whether `color` has no pawns on the files either side of `position`.
+#[inline]
+pub fn is_isolated(board: &BoardState, color: Color, position: SquarePosition) -> bool {
+    (board[(color, PieceType::Pawn)] & ADJACENT_FILES[position.to_square_index()]).is_empty()
+}
+
+/// Whether another of `color`'s pawns stands in front of `position` on the same file.
+#[inline]
+pub fn is_doubled(board: &BoardState, color: Color, position: SquarePosition) -> bool {
+    let ahead = FORWARD_FILES[color as usize][position.to_square_index()];
+
+    !(board[(color, PieceType::Pawn)] & ahead).is_empty()
+}
+
+/// Whether `color`'s pawn on `position` has a pawn of its own beside it or defending it.
+pub fn is_connected(board: &BoardState, color: Color, position: SquarePosition) -> bool {
+    let own_pawns = board[(color, PieceType::Pawn)];
+    let phalanx = own_pawns & ADJACENT_FILES[position.to_square_index()] &
+                  RANK_BOARDS[position.rank];
+    let defenders = own_pawns & PAWN_ATTACK_BOARD.get_attacks(!color, position);
+
+    !phalanx.is_empty() || !defenders.is_empty()
+}
+
+/// Whether `color`'s pawn on `position` has fallen behind the pawns on the adjacent files, so
+/// none of them can defend it, and cannot safely advance because an enemy pawn guards the
+/// square in front of it. Isolated pawns are not counted as backward.
+pub fn is_backward(board: &BoardState, color: Color, position: SquarePosition) -> bool {
+    let adjacent = board[(color, PieceType::Pawn)] & ADJACENT_FILES[position.to_square_index()];
+
+    if adjacent.is_empty() ||
+       !(adjacent & !FORWARD_RANKS[color as usize][position.rank]).is_empty() {
+        return false;
+    }
+
+    match position + pawn_direction(color) {
+        Some(stop) => {
+            !(board[(!color, PieceType::Pawn)] & PAWN_ATTACK_BOARD.get_attacks(color, stop))
+                .is_empty()
+        }
+        None => false,
+    }
+}
+
+/// The cached pawn structure of one arrangement of pawns.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PawnEntry {
+    key: u64,
+    score: Score,
+    passed: [BitBoard; 2],
+}
+
+impl PawnEntry {
+    /// The pawn key of the position the entry was computed for.
+    #[inline]
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    /// The pawn structure score for white.
+    #[inline]
+    pub fn score(&self) -> Score {
+        self.score
+    }
+
+    #[inline]
+    pub fn passed(&self, color: Color) -> BitBoard {
+        self.passed[color as usize]
+    }
+}
+
+/// Scores the pawn structure of `board` without going through a cache.
+pub fn evaluate_pawns(board: &BoardState) -> PawnEntry {
+    let mut entry = PawnEntry {
+        key: board.pawn_key(),
+        score: Score::default(),
+        passed: [EMPTY; 2],
+    };
+
+    for &color in [Color::White, Color::Black].iter() {
+        let mut score = Score::default();
+
+        for position in board[(color, PieceType::Pawn)] {
+            let rank = relative_rank(color, position);
+
+            if is_passed(board, color, position) {
+                score += PASSED_BONUS[rank];
+                entry.passed[color as usize] |= position.to_bit_board();
+            }
+            if is_isolated(board, color, position) {
+                score += ISOLATED_PENALTY;
+            }
+            if is_doubled(board, color, position) {
+                score += DOUBLED_PENALTY;
+            }
+            if is_backward(board, color, position) {
+                score += BACKWARD_PENALTY;
+            }
+            if is_connected(board, color, position) {
+                score += CONNECTED_BONUS[rank];
+            }
+        }
+
+        entry.score += match color {
+            Color::White => score,
+            Color::Black => -score,
+        };
+    }
+
+    entry
+}
+
+/// A direct mapped cache of pawn structure evaluations, indexed by the low bits of the pawn key.
+pub struct PawnHashTable {
+    entries: Vec<Option<PawnEntry>>,
+}
+
+impl PawnHashTable {
+    /// A table of at least `size` entries, rounded up to a power of two.
+    pub fn new(size: usize) -> PawnHashTable {
+        PawnHashTable { entries: vec![None; size.next_power_of_two()] }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The cached entry for the pawns of `board`, evaluating and storing it on a miss.
+    pub fn probe(&mut self, board: &BoardState) -> PawnEntry {
+        let index = board.pawn_key() as usize & (self.entries.len() - 1);
+
+        match self.entries[index] {
+            Some(entry) if entry.key == board.pawn_key() => entry,
+            _ => {
+                let entry = evaluate_pawns(board);
+                self.entries[index] = Some(entry);
+                entry
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for entry in self.entries.iter_mut() {
+            *entry = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bit_boards::BitBoard;
+    use board_state::BoardState;
+    use evaluation::pawns::{ADJACENT_FILES, PASSED_PAWN_MASKS, PawnHashTable, evaluate_pawns,
+                            is_backward, is_connected, is_doubled, is_isolated, is_passed};
+    use piece::Color;
+    use test_helpers::{board, square};
+
+    #[test]
+    fn masks_test() {
+        assert_eq!(ADJACENT_FILES[square("a4").to_square_index()], BitBoard(0x0202020202020202));
+        assert_eq!(ADJACENT_FILES[square("e1").to_square_index()], BitBoard(0x2828282828282828));
+        assert_eq!(PASSED_PAWN_MASKS[Color::White as usize][square("b6").to_square_index()],
+                   board(&["a7", "b7", "c7", "a8", "b8", "c8"]));
+        assert_eq!(PASSED_PAWN_MASKS[Color::Black as usize][square("h3").to_square_index()],
+                   board(&["g2", "h2", "g1", "h1"]));
+    }
+
+    #[test]
+    fn pawn_terms_test() {
+        let board: BoardState = "4k3/6p1/3p4/1Pp1p3/2P5/8/7P/4K3 w - - 0 1".parse().unwrap();
+
+        assert!(is_passed(&board, Color::White, square("b5")));
+        assert!(!is_passed(&board, Color::White, square("c4")));
+        assert!(!is_passed(&board, Color::White, square("h2")));
+        assert!(is_passed(&board, Color::Black, square("e5")));
+        assert!(!is_passed(&board, Color::Black, square("c5")));
+        assert!(is_isolated(&board, Color::White, square("h2")));
+        assert!(is_isolated(&board, Color::Black, square("g7")));
+        assert!(!is_isolated(&board, Color::White, square("c4")));
+        assert!(is_connected(&board, Color::White, square("b5")));
+        assert!(!is_connected(&board, Color::White, square("c4")));
+        assert!(is_connected(&board, Color::Black, square("c5")));
+        assert!(is_backward(&board, Color::Black, square("d6")));
+        assert!(!is_backward(&board, Color::Black, square("c5")));
+        assert!(!is_backward(&board, Color::White, square("h2")));
+
+        let doubled: BoardState = "4k3/8/8/8/2P5/2P5/2P5/4K3 w - - 0 1".parse().unwrap();
+        assert!(is_doubled(&doubled, Color::White, square("c2")));
+        assert!(is_doubled(&doubled, Color::White, square("c3")));
+        assert!(!is_doubled(&doubled, Color::White, square("c4")));
+        assert!(is_passed(&doubled, Color::White, square("c4")));
+        assert!(!is_passed(&doubled, Color::White, square("c3")));
+    }
+
+    #[test]
+    fn evaluate_pawns_test() {
+        let position: BoardState = "4k3/6p1/3p4/1Pp1p3/2P5/8/7P/4K3 w - - 0 1".parse().unwrap();
+        let entry = evaluate_pawns(&position);
+
+        assert_eq!(entry.passed(Color::White), board(&["b5"]));
+        assert_eq!(entry.passed(Color::Black), board(&["e5"]));
+        assert_eq!(evaluate_pawns(&position.flip_colors()).score(), -entry.score());
+
+        let better: BoardState = "4k3/pp3pp1/8/8/8/8/PPP2PPP/4K3 w - - 0 1".parse().unwrap();
+        let worse: BoardState = "4k3/ppp2ppp/8/8/8/8/P1P1P1PP/4K3 w - - 0 1".parse().unwrap();
+        assert!(evaluate_pawns(&better).score().midgame >
+                evaluate_pawns(&worse).score().midgame);
+    }
+
+    #[test]
+    fn pawn_hash_table_test() {
+        let mut table = PawnHashTable::new(1000);
+        assert_eq!(table.len(), 1024);
+
+        let mut board: BoardState =
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+        let expected = evaluate_pawns(&board);
+
+        assert_eq!(table.probe(&board), expected);
+        assert_eq!(table.probe(&board), expected);
+
+        for chess_move in board.legal_moves() {
+            board.make_move(&chess_move);
+            assert_eq!(table.probe(&board), evaluate_pawns(&board));
+            board.unmake_move(&chess_move);
+        }
+
+        table.clear();
+        assert_eq!(table.probe(&board), expected);
+    }
+}
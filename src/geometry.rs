@@ -0,0 +1,215 @@
+//! Precomputed square geometry: rays, the squares between and the line through two squares, and
+//! distances between squares.
+
+use std::convert::TryFrom;
+
+use bit_boards::{BitBoard, EMPTY};
+use square_position::{Direction, SquarePosition};
+
+/// The eight ray directions, clockwise from north, indexing `RAYS`.
+pub const RAY_DIRECTIONS: [Direction; 8] = [Direction(1, 0),
+                                            Direction(1, 1),
+                                            Direction(0, 1),
+                                            Direction(-1, 1),
+                                            Direction(-1, 0),
+                                            Direction(-1, -1),
+                                            Direction(0, -1),
+                                            Direction(1, -1)];
+
+lazy_static! {
+    /// The squares from each square to the edge of the board, excluding the square itself,
+    /// indexed by the position of the direction in `RAY_DIRECTIONS` then square.
+    pub static ref RAYS: [[BitBoard; 64]; 8] = {
+        let mut rays = [[EMPTY; 64]; 8];
+
+        for (direction_index, &direction) in RAY_DIRECTIONS.iter().enumerate() {
+            for square_index in 0..64 {
+                let mut position = SquarePosition::try_from(square_index).unwrap();
+
+                while let Some(next) = position + direction {
+                    rays[direction_index][square_index] |= next.to_bit_board();
+                    position = next;
+                }
+            }
+        }
+
+        rays
+    };
+    pub static ref BETWEEN: [[BitBoard; 64]; 64] = {
+        let mut between = [[EMPTY; 64]; 64];
+
+        for direction_index in 0..8 {
+            for start in 0..64 {
+                let ray = RAYS[direction_index][start];
+
+                for end in 0..64 {
+                    let end_board = BitBoard(1 << end);
+
+                    if !(ray & end_board).is_empty() {
+                        between[start][end] = ray & !RAYS[direction_index][end] & !end_board;
+                    }
+                }
+            }
+        }
+
+        between
+    };
+    pub static ref LINE: [[BitBoard; 64]; 64] = {
+        let mut line = [[EMPTY; 64]; 64];
+
+        for direction_index in 0..8 {
+            let opposite = (direction_index + 4) % 8;
+
+            for start in 0..64 {
+                let ray = RAYS[direction_index][start];
+
+                for end in 0..64 {
+                    if !(ray & BitBoard(1 << end)).is_empty() {
+                        line[start][end] = ray | RAYS[opposite][start] | BitBoard(1 << start);
+                    }
+                }
+            }
+        }
+
+        line
+    };
+    pub static ref CHEBYSHEV_DISTANCE: [[u8; 64]; 64] = {
+        let mut distance = [[0u8; 64]; 64];
+
+        for start in 0..64 {
+            for end in 0..64 {
+                let (rank_distance, file_distance) = rank_file_distance(start, end);
+                distance[start][end] = if rank_distance > file_distance {
+                    rank_distance
+                } else {
+                    file_distance
+                };
+            }
+        }
+
+        distance
+    };
+    pub static ref MANHATTAN_DISTANCE: [[u8; 64]; 64] = {
+        let mut distance = [[0u8; 64]; 64];
+
+        for start in 0..64 {
+            for end in 0..64 {
+                let (rank_distance, file_distance) = rank_file_distance(start, end);
+                distance[start][end] = rank_distance + file_distance;
+            }
+        }
+
+        distance
+    };
+}
+
+fn rank_file_distance(start: usize, end: usize) -> (u8, u8) {
+    let rank_distance = (start as i32 / 8 - end as i32 / 8).abs();
+    let file_distance = (start as i32 % 8 - end as i32 % 8).abs();
+
+    (rank_distance as u8, file_distance as u8)
+}
+
+/// The position of `direction` in `RAY_DIRECTIONS`, if it is one of the eight ray directions.
+pub fn direction_index(direction: Direction) -> Option<usize> {
+    RAY_DIRECTIONS.iter()
+        .position(|ray_direction| ray_direction.0 == direction.0 && ray_direction.1 == direction.1)
+}
+
+/// The squares from `position` to the edge of the board in `direction`, or an empty board if
+/// `direction` is not one of the eight ray directions.
+#[inline]
+pub fn ray(position: SquarePosition, direction: Direction) -> BitBoard {
+    match direction_index(direction) {
+        Some(index) => RAYS[index][position.to_square_index()],
+        None => EMPTY,
+    }
+}
+
+/// The squares strictly between `start` and `end`, or an empty board if they are not on a shared
+/// rank, file or diagonal.
+#[inline]
+pub fn between(start: SquarePosition, end: SquarePosition) -> BitBoard {
+    BETWEEN[start.to_square_index()][end.to_square_index()]
+}
+
+/// The whole rank, file or diagonal through `start` and `end`, edge to edge, or an empty board if
+/// they are not aligned.
+#[inline]
+pub fn line(start: SquarePosition, end: SquarePosition) -> BitBoard {
+    LINE[start.to_square_index()][end.to_square_index()]
+}
+
+/// Whether `first`, `second` and `third` all lie on one rank, file or diagonal.
+#[inline]
+pub fn aligned(first: SquarePosition, second: SquarePosition, third: SquarePosition) -> bool {
+    !(line(first, second) & third.to_bit_board()).is_empty()
+}
+
+/// The number of king moves between the two squares.
+#[inline]
+pub fn chebyshev_distance(start: SquarePosition, end: SquarePosition) -> usize {
+    CHEBYSHEV_DISTANCE[start.to_square_index()][end.to_square_index()] as usize
+}
+
+/// The number of rook steps of one square between the two squares.
+#[inline]
+pub fn manhattan_distance(start: SquarePosition, end: SquarePosition) -> usize {
+    MANHATTAN_DISTANCE[start.to_square_index()][end.to_square_index()] as usize
+}
+
+#[cfg(test)]
+mod test {
+    use bit_boards::{BitBoard, EMPTY};
+    use geometry::{aligned, between, chebyshev_distance, direction_index, line,
+                   manhattan_distance, ray};
+    use square_position::{Direction, EAST, NORTH, SOUTH, WEST};
+    use test_helpers::{board, square};
+
+    #[test]
+    fn ray_test() {
+        assert_eq!(ray(square("e4"), NORTH), board(&["e5", "e6", "e7", "e8"]));
+        assert_eq!(ray(square("e4"), SOUTH + WEST), board(&["d3", "c2", "b1"]));
+        assert_eq!(ray(square("a1"), WEST), EMPTY);
+        assert_eq!(ray(square("h8"), Direction(-1, -1)).len(), 7);
+        assert_eq!(ray(square("e4"), NORTH + NORTH + EAST), EMPTY);
+        assert_eq!(direction_index(EAST), Some(2));
+        assert_eq!(direction_index(Direction(2, 0)), None);
+    }
+
+    #[test]
+    fn between_test() {
+        assert_eq!(between(square("a1"), square("a4")), board(&["a2", "a3"]));
+        assert_eq!(between(square("a4"), square("a1")), board(&["a2", "a3"]));
+        assert_eq!(between(square("b2"), square("f6")), board(&["c3", "d4", "e5"]));
+        assert_eq!(between(square("h1"), square("a8")).len(), 6);
+        assert_eq!(between(square("c1"), square("f1")), board(&["d1", "e1"]));
+        assert_eq!(between(square("e4"), square("e5")), EMPTY);
+        assert_eq!(between(square("e4"), square("e4")), EMPTY);
+        assert_eq!(between(square("a1"), square("b3")), EMPTY);
+    }
+
+    #[test]
+    fn line_test() {
+        assert_eq!(line(square("c3"), square("e5")), BitBoard(0x8040201008040201));
+        assert_eq!(line(square("e5"), square("c3")), BitBoard(0x8040201008040201));
+        assert_eq!(line(square("b4"), square("g4")), BitBoard(0x00000000ff000000));
+        assert_eq!(line(square("d1"), square("d2")), BitBoard(0x0808080808080808));
+        assert_eq!(line(square("a1"), square("b3")), EMPTY);
+        assert_eq!(line(square("e4"), square("e4")), EMPTY);
+
+        assert!(aligned(square("a1"), square("c3"), square("h8")));
+        assert!(aligned(square("e1"), square("e8"), square("e4")));
+        assert!(!aligned(square("e1"), square("e8"), square("d4")));
+    }
+
+    #[test]
+    fn distance_test() {
+        assert_eq!(chebyshev_distance(square("a1"), square("h8")), 7);
+        assert_eq!(chebyshev_distance(square("e4"), square("g5")), 2);
+        assert_eq!(chebyshev_distance(square("e4"), square("e4")), 0);
+        assert_eq!(manhattan_distance(square("a1"), square("h8")), 14);
+        assert_eq!(manhattan_distance(square("e4"), square("g5")), 3);
+        assert_eq!(manhattan_distance(square("g5"), square("e4")), 3);
+    }
+}
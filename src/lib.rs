@@ -1,12 +1,13 @@
 #![feature(try_from)]
 #![feature(box_syntax)]
+#![feature(asm)]
+#![feature(cfg_target_feature)]
 
 
 #[macro_use]
 extern crate bitflags;
 #[macro_use]
 extern crate lazy_static;
-extern crate unicode_segmentation;
 
 pub mod bit_boards;
 pub mod piece_board;
@@ -19,3 +20,15 @@ pub mod square_position;
 pub mod error_types;
 pub mod rkiss;
 pub mod move_gen;
+pub mod zobrist;
+pub mod outcome;
+pub mod san;
+pub mod uci;
+pub mod pgn;
+pub mod see;
+pub mod sliding_attacks;
+pub mod geometry;
+pub mod evaluation;
+
+#[cfg(test)]
+mod test_helpers;
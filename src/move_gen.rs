@@ -1,15 +1,27 @@
 use std::convert::TryFrom;
 
-use bit_boards::{BitBoard, subsets_iterator};
-use square_position::{CARDINAL, Direction, EAST, INTERMEDIATE, NORTH, SOUTH, SquarePosition, WEST};
-use piece::PieceType;
-use rkiss::{MAGIC_BOOSTERS, RKISS};
-
-const ROOK_ATTACKS_SIZE: usize = 0x19000;
-const BISHOP_ATTACKS_SIZE: usize = 0x1480;
+use bit_boards::{BitBoard, EMPTY, FULL, bit_scan_forward, subsets_iterator};
+use board_state::BoardState;
+use castling::{CastleType, CastlingFlags};
+use chess_move::{Move, MoveType};
+use geometry::between;
+use square_position::{Direction, EAST, NORTH, SOUTH, SquarePosition, WEST};
+use piece::{Color, Piece, PieceType, PromotionType, SliderType, StepperType};
+#[cfg(feature = "hyperbola-quintessence")]
+use sliding_attacks::HyperbolaAttackBoard;
+#[cfg(feature = "kogge-stone")]
+use sliding_attacks::KoggeStoneAttackBoard;
+#[cfg(feature = "pext")]
+use sliding_attacks::PextAttackBoard;
+
+pub const ROOK_ATTACKS_SIZE: usize = 0x19000;
+pub const BISHOP_ATTACKS_SIZE: usize = 0x1480;
 const KING_ATTACKS_SIZE: usize = 64;
 const KNIGHT_ATTACKS_SIZE: usize = 64;
 
+// The magic bitboard tables generated by build.rs from data/magics.csv.
+include!(concat!(env!("OUT_DIR"), "/magic_tables.rs"));
+
 lazy_static! {
     pub static ref KING_DIRECTIONS: [Direction; 8] = {
         [
@@ -42,30 +54,23 @@ pub trait BitboardGenerator {
 }
 
 pub struct StepAttackBoard {
-    piece_type: PieceType,
+    stepper_type: StepperType,
     attacks: Box<[BitBoard]>,
 }
 
 impl StepAttackBoard {
-    pub fn new(piece_type: PieceType) -> StepAttackBoard {
-        let directions: &[Direction] = &match piece_type {
-            PieceType::King => *KING_DIRECTIONS,
-            PieceType::Knight => *KNIGHT_DIRECTIONS,
-            _ => panic!("Illegal piece type argument"),
-        };
-
-        let attack_array: Box<[BitBoard]> = match piece_type {
-            PieceType::King => box [0 as BitBoard; KING_ATTACKS_SIZE],
-            PieceType::Knight => box [0 as BitBoard; KNIGHT_ATTACKS_SIZE],
-            _ => panic!("Illegal piece type argument"),
+    pub fn new(stepper_type: StepperType) -> StepAttackBoard {
+        let (directions, attack_array): (&[Direction], Box<[BitBoard]>) = match stepper_type {
+            StepperType::King => (&*KING_DIRECTIONS, box [EMPTY; KING_ATTACKS_SIZE]),
+            StepperType::Knight => (&*KNIGHT_DIRECTIONS, box [EMPTY; KNIGHT_ATTACKS_SIZE]),
         };
 
         let mut step_board = StepAttackBoard {
-            piece_type: piece_type,
+            stepper_type: stepper_type,
             attacks: attack_array,
         };
 
-        step_board.generate_step_attacks(&directions);
+        step_board.generate_step_attacks(directions);
 
         step_board
     }
@@ -78,7 +83,7 @@ impl StepAttackBoard {
             .map(|(orig, new_pos)| (orig, new_pos.unwrap()));
 
         for (original, new_pos) in steps {
-            self.attacks[original as usize] |= 1 << new_pos.to_square_index();
+            self.attacks[original as usize] |= new_pos.to_bit_board();
         }
     }
 }
@@ -89,138 +94,156 @@ impl BitboardGenerator for StepAttackBoard {
     }
 }
 
-pub struct MagicAttackBoard {
-    piece_type: PieceType,
-    shifts: [usize; 64],
-    offsets: [usize; 64],
-    masks: [BitBoard; 64],
-    magics: [BitBoard; 64],
-    attacks: Box<[BitBoard]>,
+/// Pawn capture targets and pushes for each color, indexed by color then square.
+pub struct PawnAttackBoard {
+    attacks: [[BitBoard; 64]; 2],
+    pushes: [[BitBoard; 64]; 2],
+    double_pushes: [[BitBoard; 64]; 2],
 }
 
-impl MagicAttackBoard {
-    pub fn new(piece_type: PieceType) -> MagicAttackBoard {
-        let directions: &[Direction] = &match piece_type {
-            PieceType::Rook => CARDINAL,
-            PieceType::Bishop => INTERMEDIATE,
-            _ => panic!("Illegal piece type argument"),
+impl PawnAttackBoard {
+    pub fn new() -> PawnAttackBoard {
+        let mut pawn_board = PawnAttackBoard {
+            attacks: [[EMPTY; 64]; 2],
+            pushes: [[EMPTY; 64]; 2],
+            double_pushes: [[EMPTY; 64]; 2],
         };
 
-        let attack_array: Box<[BitBoard]> = match piece_type {
-            PieceType::Rook => box [0 as BitBoard; ROOK_ATTACKS_SIZE],
-            PieceType::Bishop => box [0 as BitBoard; BISHOP_ATTACKS_SIZE],
-            _ => panic!("Illegal piece type argument"),
-        };
+        for &color in [Color::White, Color::Black].iter() {
+            let forward = pawn_direction(color);
+            let start_rank = match color {
+                Color::White => 1,
+                Color::Black => 6,
+            };
 
-        let mut magic_board = MagicAttackBoard {
-            piece_type: piece_type,
-            masks: [0 as BitBoard; 64],
-            shifts: [0 as usize; 64],
-            offsets: [0 as usize; 64],
-            magics: [0 as BitBoard; 64],
-            attacks: attack_array,
-        };
+            for square_index in 0..64 {
+                let position = SquarePosition::try_from(square_index).unwrap();
+
+                for &direction in [forward + EAST, forward + WEST].iter() {
+                    if let Some(attacked) = position + direction {
+                        pawn_board.attacks[color as usize][square_index] |=
+                            attacked.to_bit_board();
+                    }
+                }
+
+                if let Some(push) = position + forward {
+                    pawn_board.pushes[color as usize][square_index] = push.to_bit_board();
+
+                    if position.rank == start_rank {
+                        pawn_board.double_pushes[color as usize][square_index] =
+                            (push + forward).unwrap().to_bit_board();
+                    }
+                }
+            }
+        }
+
+        pawn_board
+    }
+
+    #[inline]
+    pub fn get_attacks(&self, color: Color, position: SquarePosition) -> BitBoard {
+        self.attacks[color as usize][position.to_square_index()]
+    }
 
-        magic_board.generate_magics(MAGIC_BOOSTERS, directions);
+    /// The squares a pawn can push to given `occupancy`, including the double push from its
+    /// starting rank when both squares in front of it are empty.
+    #[inline]
+    pub fn get_pushes(&self,
+                      color: Color,
+                      position: SquarePosition,
+                      occupancy: BitBoard)
+                      -> BitBoard {
+        let square_index = position.to_square_index();
+        let push = self.pushes[color as usize][square_index] & !occupancy;
 
-        magic_board
+        if push.is_empty() {
+            EMPTY
+        } else {
+            push | (self.double_pushes[color as usize][square_index] & !occupancy)
+        }
+    }
+}
+
+pub struct MagicAttackBoard {
+    slider_type: SliderType,
+    black: bool,
+    shifts: &'static [usize; 64],
+    offsets: &'static [usize; 64],
+    masks: &'static [BitBoard; 64],
+    magics: &'static [u64; 64],
+    attacks: &'static [BitBoard],
+}
+
+impl MagicAttackBoard {
+    /// Wraps the tables that build.rs generates from the magics in data/magics.csv, which may be
+    /// plain magics with a table per square or black magics sharing one table.
+    pub fn new(slider_type: SliderType) -> MagicAttackBoard {
+        match slider_type {
+            SliderType::Rook => {
+                MagicAttackBoard {
+                    slider_type: slider_type,
+                    black: ROOK_BLACK,
+                    shifts: &ROOK_SHIFTS,
+                    offsets: &ROOK_OFFSETS,
+                    masks: &ROOK_MASKS,
+                    magics: &ROOK_MAGICS,
+                    attacks: &ROOK_ATTACKS,
+                }
+            }
+            SliderType::Bishop => {
+                MagicAttackBoard {
+                    slider_type: slider_type,
+                    black: BISHOP_BLACK,
+                    shifts: &BISHOP_SHIFTS,
+                    offsets: &BISHOP_OFFSETS,
+                    masks: &BISHOP_MASKS,
+                    magics: &BISHOP_MAGICS,
+                    attacks: &BISHOP_ATTACKS,
+                }
+            }
+        }
     }
 
     pub fn piece_type(&self) -> PieceType {
-        self.piece_type
+        self.slider_type.piece_type()
+    }
+
+    /// Whether the magics are black magics, which index `occupancy | !mask` rather than
+    /// `occupancy & mask`.
+    pub fn is_black(&self) -> bool {
+        self.black
     }
 
     pub fn masks(&self) -> &[BitBoard] {
-        &self.masks
+        self.masks
     }
 
     pub fn shifts(&self) -> &[usize] {
-        &self.shifts
+        self.shifts
     }
 
     pub fn offsets(&self) -> &[usize] {
-        &self.offsets
+        self.offsets
     }
 
-    pub fn magics(&self) -> &[BitBoard] {
-        &self.magics
+    pub fn magics(&self) -> &[u64] {
+        self.magics
     }
 
     pub fn attacks(&self) -> &[BitBoard] {
-        &self.attacks
+        self.attacks
     }
 
     pub fn compute_index(&self, position: SquarePosition, occupancy: BitBoard) -> usize {
         let square_index = position.to_square_index();
-        self.offsets[square_index] +
-        (((occupancy & self.masks[square_index]).wrapping_mul(self.magics[square_index])) >>
-         self.shifts[square_index]) as usize
-    }
-
-    fn generate_magics(&mut self, boosters: [usize; 8], directions: &[Direction]) {
-        let mut rkiss = RKISS::new(203);
-        let mut reference = [0 as BitBoard; 4096];
-        let mut occupancy = [0 as BitBoard; 4096];
-
-        self.offsets[0] = 0;
-
-        for square_index in 0..64 {
-            let random_booster = boosters[(square_index >> 3) as usize];
-
-            self.masks[square_index] = generate_mask(square_index, directions, false);
-            self.shifts[square_index] = 64 - (self.masks[square_index].count_ones() as usize);
-
-            let mut size = 0;
-            for (subset_index, subset) in subsets_iterator(&self.masks[square_index]).enumerate() {
-                occupancy[subset_index] = subset;
-                reference[subset_index] =
-                    generate_sliding_attack_bitboard(directions,
-                                                     SquarePosition::try_from(square_index)
-                                                         .unwrap(),
-                                                     subset);
-
-                size += 1;
-            }
-
-            if square_index < 63 {
-                self.offsets[square_index + 1] = self.offsets[square_index] + size;
-            }
-
-            let index_range = self.offsets[square_index]..(self.offsets[square_index] + size);
-            loop {
-                self.magics[square_index] =
-                    magic_few_bits(random_booster, &mut rkiss, self.masks[square_index]);
-
-                for value in &mut self.attacks[index_range.clone()] {
-                    *value = 0;
-                }
-
-                let mut failed = false;
-
-                for index in 0..size {
-                    let magic_index = magic_index(self.offsets[square_index],
-                                                  self.shifts[square_index],
-                                                  occupancy[index],
-                                                  self.masks[square_index],
-                                                  self.magics[square_index]);
-
-                    if self.attacks[magic_index] != 0 &&
-                       self.attacks[magic_index] != reference[index] {
-                        failed = true;
-                        break;
-                    }
-
-                    assert!(reference[index] != 0);
-
-                    self.attacks[magic_index] = reference[index];
-                }
-
+        let key = if self.black {
+            occupancy | !self.masks[square_index]
+        } else {
+            occupancy & self.masks[square_index]
+        };
 
-                if !failed {
-                    break;
-                }
-            }
-        }
+        self.offsets[square_index] +
+        (key.bits().wrapping_mul(self.magics[square_index]) >> self.shifts[square_index]) as usize
     }
 }
 
@@ -230,42 +253,41 @@ impl BitboardGenerator for MagicAttackBoard {
     }
 }
 
-fn magic_index(offset: usize,
-               shift: usize,
-               occupancy: BitBoard,
-               mask: BitBoard,
-               magic: BitBoard)
-               -> usize {
-    offset + (((occupancy & mask).wrapping_mul(magic)) >> shift) as usize
+/// Queen attacks as the union of the rook and bishop attacks from the same square.
+pub struct QueenAttackBoard {
+    rook: &'static SlidingAttackBoard,
+    bishop: &'static SlidingAttackBoard,
 }
 
-fn magic_few_bits(random_booster: usize, rkiss: &mut RKISS, mask: BitBoard) -> BitBoard {
-    let mut magic = 0;
-
-    loop {
-        magic = rkiss.magic_rand(random_booster) as BitBoard;
-
-        if ((magic.wrapping_mul(mask)) >> 56).count_ones() < 6 {
-            break;
+impl QueenAttackBoard {
+    /// Combines the shared rook and bishop boards rather than building tables of its own.
+    pub fn new() -> QueenAttackBoard {
+        QueenAttackBoard {
+            rook: &*ROOK_ATTACK_BOARD,
+            bishop: &*BISHOP_ATTACK_BOARD,
         }
     }
+}
 
-    magic
+impl BitboardGenerator for QueenAttackBoard {
+    fn get_attacks(&self, position: SquarePosition, occupancy: BitBoard) -> BitBoard {
+        self.rook.get_attacks(position, occupancy) | self.bishop.get_attacks(position, occupancy)
+    }
 }
 
 pub fn generate_mask(square_index: usize, directions: &[Direction], edges: bool) -> BitBoard {
-    let mut result: BitBoard = 0;
+    let mut result = EMPTY;
     if let Ok(position) = TryFrom::try_from(square_index) {
         for direction in directions {
             let mut rook_position: SquarePosition = position;
             while let Some(new_pos) = rook_position + *direction {
                 rook_position = new_pos;
 
-                result |= 1 << rook_position.to_square_index();
+                result |= rook_position.to_bit_board();
             }
 
             if !edges {
-                result &= !(1 << rook_position.to_square_index());
+                result &= !rook_position.to_bit_board();
             }
         }
     }
@@ -302,7 +324,7 @@ fn generate_sliding_attack_bitboard(directions: &[Direction],
                                     position: SquarePosition,
                                     occupied: BitBoard)
                                     -> BitBoard {
-    let mut result: BitBoard = 0;
+    let mut result = EMPTY;
 
     for &direction in directions {
         let mut ray_index = position.clone();
@@ -310,7 +332,7 @@ fn generate_sliding_attack_bitboard(directions: &[Direction],
             result |= new_pos.to_bit_board();
             ray_index = new_pos;
 
-            if (occupied & new_pos.to_bit_board()) != 0 {
+            if !(occupied & new_pos.to_bit_board()).is_empty() {
                 break;
             }
         }
@@ -319,24 +341,433 @@ fn generate_sliding_attack_bitboard(directions: &[Direction],
     result
 }
 
+/// The sliding attack backend used for move generation, chosen with the `pext`,
+/// `hyperbola-quintessence` or `kogge-stone` feature in that order of precedence.
+#[cfg(feature = "pext")]
+pub type SlidingAttackBoard = PextAttackBoard;
+#[cfg(all(feature = "hyperbola-quintessence", not(feature = "pext")))]
+pub type SlidingAttackBoard = HyperbolaAttackBoard;
+#[cfg(all(feature = "kogge-stone",
+          not(any(feature = "pext", feature = "hyperbola-quintessence"))))]
+pub type SlidingAttackBoard = KoggeStoneAttackBoard;
+#[cfg(not(any(feature = "pext", feature = "hyperbola-quintessence", feature = "kogge-stone")))]
+pub type SlidingAttackBoard = MagicAttackBoard;
+
+lazy_static! {
+    pub static ref KING_ATTACK_BOARD: StepAttackBoard = StepAttackBoard::new(StepperType::King);
+    pub static ref KNIGHT_ATTACK_BOARD: StepAttackBoard = StepAttackBoard::new(StepperType::Knight);
+    pub static ref PAWN_ATTACK_BOARD: PawnAttackBoard = PawnAttackBoard::new();
+    pub static ref ROOK_ATTACK_BOARD: SlidingAttackBoard =
+        SlidingAttackBoard::new(SliderType::Rook);
+    pub static ref BISHOP_ATTACK_BOARD: SlidingAttackBoard =
+        SlidingAttackBoard::new(SliderType::Bishop);
+    pub static ref QUEEN_ATTACK_BOARD: QueenAttackBoard = QueenAttackBoard::new();
+}
+
+const PROMOTION_TYPES: [PromotionType; 4] =
+    [PromotionType::Queen, PromotionType::Rook, PromotionType::Bishop, PromotionType::Knight];
+
+struct MoveFilter {
+    king: Option<SquarePosition>,
+    check_mask: BitBoard,
+    pinned: BitBoard,
+    pin_rays: [BitBoard; 64],
+}
+
+impl MoveFilter {
+    fn unrestricted() -> MoveFilter {
+        MoveFilter {
+            king: None,
+            check_mask: FULL,
+            pinned: EMPTY,
+            pin_rays: [FULL; 64],
+        }
+    }
+
+    fn legal(board: &BoardState, king: SquarePosition, checkers: BitBoard) -> MoveFilter {
+        let color = board.active_color();
+        let occupancy = board.occupancy();
+
+        let check_mask = match bit_scan_forward(checkers) {
+            Some(checker_index) => {
+                let checker = SquarePosition::try_from(checker_index).unwrap();
+                checker.to_bit_board() | between(king, checker)
+            }
+            None => FULL,
+        };
+
+        let mut filter = MoveFilter {
+            king: Some(king),
+            check_mask: check_mask,
+            pinned: EMPTY,
+            pin_rays: [FULL; 64],
+        };
+
+        let queens = board[(!color, PieceType::Queen)];
+        let snipers = (ROOK_ATTACK_BOARD.get_attacks(king, board[!color]) &
+                       (board[(!color, PieceType::Rook)] | queens)) |
+                      (BISHOP_ATTACK_BOARD.get_attacks(king, board[!color]) &
+                       (board[(!color, PieceType::Bishop)] | queens));
+
+        for sniper in snipers {
+            let ray = between(king, sniper);
+            let blockers = ray & occupancy;
+
+            if blockers.len() == 1 && !(blockers & board[color]).is_empty() {
+                filter.pinned |= blockers;
+                filter.pin_rays[bit_scan_forward(blockers).unwrap()] = ray | sniper.to_bit_board();
+            }
+        }
+
+        filter
+    }
+
+    #[inline]
+    fn targets(&self, start: SquarePosition) -> BitBoard {
+        if !(self.pinned & start.to_bit_board()).is_empty() {
+            self.check_mask & self.pin_rays[start.to_square_index()]
+        } else {
+            self.check_mask
+        }
+    }
+}
+
+pub fn generate_pseudo_legal_moves(board: &BoardState) -> Vec<Move> {
+    let mut moves = Vec::new();
+    let filter = MoveFilter::unrestricted();
+
+    generate_pawn_moves(board, &filter, &mut moves);
+    generate_piece_moves(board, PieceType::Knight, &filter, &mut moves);
+    generate_piece_moves(board, PieceType::Bishop, &filter, &mut moves);
+    generate_piece_moves(board, PieceType::Rook, &filter, &mut moves);
+    generate_piece_moves(board, PieceType::Queen, &filter, &mut moves);
+    generate_piece_moves(board, PieceType::King, &filter, &mut moves);
+
+    generate_castling_moves(board, &mut moves);
+
+    moves
+}
+
+pub fn generate_legal_moves(board: &BoardState) -> Vec<Move> {
+    let color = board.active_color();
+    let king = match bit_scan_forward(board[(color, PieceType::King)]) {
+        Some(king_index) => SquarePosition::try_from(king_index).unwrap(),
+        None => return generate_pseudo_legal_moves(board),
+    };
+
+    let mut moves = Vec::new();
+    let checkers = checkers(board);
+
+    generate_king_moves(board, king, &mut moves);
+
+    if checkers.len() > 1 {
+        return moves;
+    }
+
+    let filter = MoveFilter::legal(board, king, checkers);
+
+    generate_pawn_moves(board, &filter, &mut moves);
+    generate_piece_moves(board, PieceType::Knight, &filter, &mut moves);
+    generate_piece_moves(board, PieceType::Bishop, &filter, &mut moves);
+    generate_piece_moves(board, PieceType::Rook, &filter, &mut moves);
+    generate_piece_moves(board, PieceType::Queen, &filter, &mut moves);
+
+    if checkers.is_empty() {
+        generate_castling_moves(board, &mut moves);
+    }
+
+    moves
+}
+
+pub fn checkers(board: &BoardState) -> BitBoard {
+    let color = board.active_color();
+
+    match bit_scan_forward(board[(color, PieceType::King)]) {
+        Some(king_index) => {
+            attackers(board,
+                      SquarePosition::try_from(king_index).unwrap(),
+                      !color,
+                      board.occupancy())
+        }
+        None => EMPTY,
+    }
+}
+
+pub fn pinned(board: &BoardState) -> BitBoard {
+    let color = board.active_color();
+
+    match bit_scan_forward(board[(color, PieceType::King)]) {
+        Some(king_index) => {
+            let king = SquarePosition::try_from(king_index).unwrap();
+            MoveFilter::legal(board, king, checkers(board)).pinned
+        }
+        None => EMPTY,
+    }
+}
+
+/// The squares `piece` attacks from `position` given `occupancy`. For pawns these are the capture
+/// targets; pushes come from `PawnAttackBoard::get_pushes`.
+pub fn attacks(piece: Piece, position: SquarePosition, occupancy: BitBoard) -> BitBoard {
+    match piece.piece_type() {
+        PieceType::King => KING_ATTACK_BOARD.get_attacks(position, occupancy),
+        PieceType::Knight => KNIGHT_ATTACK_BOARD.get_attacks(position, occupancy),
+        PieceType::Bishop => BISHOP_ATTACK_BOARD.get_attacks(position, occupancy),
+        PieceType::Rook => ROOK_ATTACK_BOARD.get_attacks(position, occupancy),
+        PieceType::Queen => QUEEN_ATTACK_BOARD.get_attacks(position, occupancy),
+        PieceType::Pawn => pawn_attacks(piece.color(), position),
+    }
+}
+
+#[inline]
+fn pawn_attacks(color: Color, position: SquarePosition) -> BitBoard {
+    PAWN_ATTACK_BOARD.get_attacks(color, position)
+}
+
+/// The direction `color`'s pawns move in.
+#[inline]
+pub fn pawn_direction(color: Color) -> Direction {
+    match color {
+        Color::White => NORTH,
+        Color::Black => SOUTH,
+    }
+}
+
+fn attackers(board: &BoardState,
+             position: SquarePosition,
+             by_color: Color,
+             occupancy: BitBoard)
+             -> BitBoard {
+    let queens = board[(by_color, PieceType::Queen)];
+    let diagonal = board[(by_color, PieceType::Bishop)] | queens;
+    let orthogonal = board[(by_color, PieceType::Rook)] | queens;
+
+    let attackers = (pawn_attacks(!by_color, position) & board[(by_color, PieceType::Pawn)]) |
+                    (KNIGHT_ATTACK_BOARD.get_attacks(position, occupancy) &
+                     board[(by_color, PieceType::Knight)]) |
+                    (KING_ATTACK_BOARD.get_attacks(position, occupancy) &
+                     board[(by_color, PieceType::King)]) |
+                    (BISHOP_ATTACK_BOARD.get_attacks(position, occupancy) & diagonal) |
+                    (ROOK_ATTACK_BOARD.get_attacks(position, occupancy) & orthogonal);
+
+    attackers & occupancy
+}
+
+/// Every piece of either color attacking `position`, with sliders blocked by `occupancy`.
+pub fn attackers_to(board: &BoardState,
+                    position: SquarePosition,
+                    occupancy: BitBoard)
+                    -> BitBoard {
+    attackers(board, position, Color::White, occupancy) |
+    attackers(board, position, Color::Black, occupancy)
+}
+
+#[inline]
+pub fn square_attacked(board: &BoardState,
+                   position: SquarePosition,
+                   by_color: Color,
+                   occupancy: BitBoard)
+                   -> bool {
+    !attackers(board, position, by_color, occupancy).is_empty()
+}
+
+fn push_move(board: &BoardState,
+             start: SquarePosition,
+             end: SquarePosition,
+             active: Piece,
+             moves: &mut Vec<Move>) {
+    let move_type = match board.piece_at(end) {
+        Some(captured) => MoveType::Capture(captured),
+        None => MoveType::Quiet,
+    };
+
+    moves.push(Move::new(start, end, active, move_type));
+}
+
+fn generate_piece_moves(board: &BoardState,
+                        piece_type: PieceType,
+                        filter: &MoveFilter,
+                        moves: &mut Vec<Move>) {
+    let color = board.active_color();
+    let active = Piece::new(piece_type, color);
+    let occupancy = board.occupancy();
+
+    for start in board[(color, piece_type)] {
+        let attacks = attacks(active, start, occupancy) & !board[color] &
+                      filter.targets(start);
+
+        for end in attacks {
+            push_move(board, start, end, active, moves);
+        }
+    }
+}
+
+fn generate_king_moves(board: &BoardState, king: SquarePosition, moves: &mut Vec<Move>) {
+    let color = board.active_color();
+    let active = Piece::new(PieceType::King, color);
+    let occupancy = board.occupancy() & !king.to_bit_board();
+    let attacks = KING_ATTACK_BOARD.get_attacks(king, occupancy) & !board[color];
+
+    for end in attacks {
+        if !square_attacked(board, end, !color, occupancy & !end.to_bit_board()) {
+            push_move(board, king, end, active, moves);
+        }
+    }
+}
+
+fn generate_pawn_moves(board: &BoardState, filter: &MoveFilter, moves: &mut Vec<Move>) {
+    let color = board.active_color();
+    let active = Piece::new(PieceType::Pawn, color);
+    let promotion_rank = match color {
+        Color::White => 7,
+        Color::Black => 0,
+    };
+    let occupancy = board.occupancy();
+    let enemies = board[!color];
+
+    for start in board[(color, PieceType::Pawn)] {
+        let targets = filter.targets(start);
+        let pushes = PAWN_ATTACK_BOARD.get_pushes(color, start, occupancy) & targets;
+
+        for end in pushes {
+            if end.rank == promotion_rank {
+                for &promotion in PROMOTION_TYPES.iter() {
+                    moves.push(Move::new(start, end, active, MoveType::Promotion(promotion)));
+                }
+            } else if end.rank == start.rank + 2 || start.rank == end.rank + 2 {
+                moves.push(Move::new(start, end, active, MoveType::DoublePawnPush));
+            } else {
+                moves.push(Move::new(start, end, active, MoveType::Quiet));
+            }
+        }
+
+        for end in pawn_attacks(color, start) {
+            let end_board = end.to_bit_board();
+
+            if !(end_board & enemies & targets).is_empty() {
+                let captured = board.piece_at(end).unwrap();
+
+                if end.rank == promotion_rank {
+                    for &promotion in PROMOTION_TYPES.iter() {
+                        moves.push(Move::new(start,
+                                             end,
+                                             active,
+                                             MoveType::CapturePromotion(promotion, captured)));
+                    }
+                } else {
+                    moves.push(Move::new(start, end, active, MoveType::Capture(captured)));
+                }
+            } else if !(end_board & board.en_passant()).is_empty() &&
+                      en_passant_legal(board, filter, start, end) {
+                moves.push(Move::new(start, end, active, MoveType::EnPassant));
+            }
+        }
+    }
+}
+
+/// Checks the en passant capture by removing both pawns from the occupancy, which also catches
+/// the case of the king being exposed along the rank the two pawns shared.
+fn en_passant_legal(board: &BoardState,
+                    filter: &MoveFilter,
+                    start: SquarePosition,
+                    end: SquarePosition)
+                    -> bool {
+    match filter.king {
+        Some(king) => {
+            let color = board.active_color();
+            let captured = SquarePosition::new(start.rank, end.file);
+            let occupancy = (board.occupancy() & !start.to_bit_board() &
+                             !captured.to_bit_board()) | end.to_bit_board();
+
+            !square_attacked(board, king, !color, occupancy)
+        }
+        None => true,
+    }
+}
+
+/// All the squares on `rank` from `start_file` to `end_file` inclusive.
+fn rank_span(rank: usize, start_file: usize, end_file: usize) -> BitBoard {
+    let (low, high) = if start_file < end_file {
+        (start_file, end_file)
+    } else {
+        (end_file, start_file)
+    };
+
+    (low..high + 1).fold(EMPTY, |acc, file| acc | SquarePosition::new(rank, file).to_bit_board())
+}
+
+/// Generates castling for both standard chess and Chess960. Every square the king and the
+/// castling rook travel over must be empty apart from the two pieces themselves, and no square
+/// the king stands on or passes through may be attacked.
+fn generate_castling_moves(board: &BoardState, moves: &mut Vec<Move>) {
+    let color = board.active_color();
+    let king_start = match bit_scan_forward(board[(color, PieceType::King)]) {
+        Some(king_index) => SquarePosition::try_from(king_index).unwrap(),
+        None => return,
+    };
+    let back_rank = king_start.rank;
+    let occupancy = board.occupancy();
+    let rights = board.castling_rights();
+
+    if !rights.intersects(CastlingFlags::for_color(color)) {
+        return;
+    }
+
+    for &castle_type in [CastleType::Kingside, CastleType::Queenside].iter() {
+        if !rights.contains(CastlingFlags::for_castle(color, castle_type)) {
+            continue;
+        }
+
+        let rook_start = board.castling_rooks().position(color, castle_type);
+        if rook_start.rank != back_rank || !board[(color, PieceType::Rook)].contains(rook_start) {
+            continue;
+        }
+
+        let king_end = SquarePosition::new(back_rank, castle_type.king_end_file());
+        let king_path = rank_span(back_rank, king_start.file, king_end.file);
+        let rook_path = rank_span(back_rank, rook_start.file, castle_type.rook_end_file());
+        let others = occupancy & !king_start.to_bit_board() & !rook_start.to_bit_board();
+
+        if !((king_path | rook_path) & others).is_empty() {
+            continue;
+        }
+
+        let attacked = king_path.squares()
+            .any(|square| square_attacked(board, square, !color, others));
+
+        if !attacked {
+            moves.push(Move::new(king_start,
+                                 king_end,
+                                 Piece::new(PieceType::King, color),
+                                 MoveType::Castle(castle_type)));
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
 
+    use std::convert::TryFrom;
     use std::mem::{size_of, size_of_val};
 
-    use bit_boards::BitBoard;
-    use square_position::{CARDINAL, Direction, SquarePosition};
+    use bit_boards::{BitBoard, EMPTY, FULL, subsets_iterator};
+    use square_position::{CARDINAL, Direction, INTERMEDIATE, SquarePosition};
     use move_gen::{BISHOP_ATTACKS_SIZE, BitboardGenerator, KING_ATTACKS_SIZE, KNIGHT_ATTACKS_SIZE,
-                   MagicAttackBoard, ROOK_ATTACKS_SIZE, generate_sliding_attack_bitboard,
-                   generate_sliding_attack_bitboards};
-    use piece::PieceType;
+                   MagicAttackBoard, PAWN_ATTACK_BOARD, QUEEN_ATTACK_BOARD, ROOK_ATTACKS_SIZE,
+                   attacks, generate_legal_moves, generate_mask, generate_pseudo_legal_moves,
+                   generate_sliding_attack_bitboard, generate_sliding_attack_bitboards};
+    use board_state::BoardState;
+    use castling::CastleType;
+    use chess_move::MoveType;
+    use piece::{Color, Piece, PieceType, PromotionType, SliderType};
+    use rkiss::RKISS;
+    use test_helpers::{board, square};
 
     #[test]
     fn get_size_of_test() {
-        let rook_attacks: Box<[BitBoard]> = box [0 as BitBoard; ROOK_ATTACKS_SIZE];
-        let bishop_attacks: Box<[BitBoard]> = box [0 as BitBoard; BISHOP_ATTACKS_SIZE];
-        let knight_attacks: Box<[BitBoard]> = box [0 as BitBoard; KNIGHT_ATTACKS_SIZE];
-        let king_attacks: Box<[BitBoard]> = box [0 as BitBoard; KING_ATTACKS_SIZE];
+        let rook_attacks: Box<[BitBoard]> = box [EMPTY; ROOK_ATTACKS_SIZE];
+        let bishop_attacks: Box<[BitBoard]> = box [EMPTY; BISHOP_ATTACKS_SIZE];
+        let knight_attacks: Box<[BitBoard]> = box [EMPTY; KNIGHT_ATTACKS_SIZE];
+        let king_attacks: Box<[BitBoard]> = box [EMPTY; KING_ATTACKS_SIZE];
 
         assert_eq!(size_of_val(&*rook_attacks),
                    size_of::<BitBoard>() * ROOK_ATTACKS_SIZE);
@@ -350,7 +781,7 @@ mod test {
 
     #[test]
     fn rook_magic_index_test() {
-        let board = MagicAttackBoard::new(PieceType::Rook);
+        let board = MagicAttackBoard::new(SliderType::Rook);
 
         let positions: Vec<SquarePosition> = [(2, 4), (4, 2), (6, 2), (2, 1), (7, 4)]
             .iter()
@@ -358,7 +789,10 @@ mod test {
             .collect();
 
         let occupancies: Vec<BitBoard> =
-            vec![0x4114442008204d3, 0x101412c000024491, 0x70400080818181d7, 0x2000000080200, 0x0];
+            vec![0x4114442008204d3, 0x101412c000024491, 0x70400080818181d7, 0x2000000080200, 0x0]
+                .into_iter()
+                .map(BitBoard)
+                .collect();
 
         let parameters: Vec<(SquarePosition, BitBoard)> =
             positions.into_iter().zip(occupancies.into_iter()).collect();
@@ -378,4 +812,209 @@ mod test {
             assert_eq!(magic_attack, reference_attack);
         }
     }
+
+    #[test]
+    fn embedded_magic_tables_test() {
+        let boards = [(MagicAttackBoard::new(SliderType::Rook), CARDINAL, ROOK_ATTACKS_SIZE),
+                      (MagicAttackBoard::new(SliderType::Bishop),
+                       INTERMEDIATE,
+                       BISHOP_ATTACKS_SIZE)];
+
+        let mut rkiss = RKISS::new(5);
+
+        for &(ref board, directions, size) in boards.iter() {
+            if board.is_black() {
+                assert!(board.attacks().len() < size);
+            } else {
+                assert_eq!(board.attacks().len(), size);
+            }
+
+            for square_index in 0..64 {
+                let position = SquarePosition::try_from(square_index).unwrap();
+                assert_eq!(board.masks()[square_index],
+                           generate_mask(square_index, &directions, false));
+
+                // Every occupancy is some subset of the mask plus squares outside it, including the
+                // edges, which must not affect the attacks whatever the square's offset is.
+                let unmasked = !board.masks()[square_index];
+                for occupancy in subsets_iterator(&board.masks()[square_index]) {
+                    let expected =
+                        generate_sliding_attack_bitboard(&directions, position, occupancy);
+                    let outside = BitBoard(rkiss.rand()) & unmasked;
+
+                    assert_eq!(board.get_attacks(position, occupancy), expected);
+                    assert_eq!(board.get_attacks(position, occupancy | unmasked), expected);
+                    assert_eq!(board.get_attacks(position, occupancy | outside), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn starting_pseudo_legal_moves_test() {
+        let board: BoardState = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+            .parse()
+            .unwrap();
+        let moves = generate_pseudo_legal_moves(&board);
+
+        assert_eq!(moves.len(), 20);
+        assert_eq!(moves.iter()
+                       .filter(|chess_move| *chess_move.move_type() == MoveType::DoublePawnPush)
+                       .count(),
+                   8);
+    }
+
+    #[test]
+    fn castling_pseudo_legal_moves_test() {
+        let board: BoardState = "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1".parse().unwrap();
+        let castles: Vec<CastleType> = generate_pseudo_legal_moves(&board)
+            .into_iter()
+            .filter_map(|chess_move| match *chess_move.move_type() {
+                MoveType::Castle(castle_type) => Some(castle_type),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(castles, vec![CastleType::Kingside, CastleType::Queenside]);
+
+        let attacked: BoardState = "r3k2r/8/8/8/8/8/8/R3KR1R b KQkq - 0 1".parse().unwrap();
+        assert!(!generate_pseudo_legal_moves(&attacked)
+            .iter()
+            .any(|chess_move| *chess_move.move_type() == MoveType::Castle(CastleType::Kingside)));
+    }
+
+    #[test]
+    fn pawn_special_pseudo_legal_moves_test() {
+        let board: BoardState = "1n5k/P7/8/3Pp3/8/8/8/K7 w - e6 0 1".parse().unwrap();
+        let moves = generate_pseudo_legal_moves(&board);
+
+        let knight = "n".parse::<Piece>().unwrap();
+        assert!(moves.iter().any(|chess_move| {
+            *chess_move.move_type() == MoveType::CapturePromotion(PromotionType::Queen, knight)
+        }));
+        assert_eq!(moves.iter()
+                       .filter(|chess_move| match *chess_move.move_type() {
+                           MoveType::Promotion(_) => true,
+                           _ => false,
+                       })
+                       .count(),
+                   4);
+        assert!(moves.iter().any(|chess_move| {
+            *chess_move.move_type() == MoveType::EnPassant &&
+            chess_move.end().to_string() == "e6"
+        }));
+    }
+
+    #[test]
+    fn double_check_legal_moves_test() {
+        let board: BoardState = "4r2k/8/8/8/8/R2n4/8/4K3 w - - 0 1".parse().unwrap();
+        let moves = generate_legal_moves(&board);
+
+        assert_eq!(board.checkers().len(), 2);
+        assert_eq!(moves.len(), 3);
+        assert!(moves.iter().all(|chess_move| chess_move.active().piece_type() == PieceType::King));
+    }
+
+    #[test]
+    fn pinned_legal_moves_test() {
+        let bishop_pinned: BoardState = "4r2k/8/8/8/8/8/4B3/4K3 w - - 0 1".parse().unwrap();
+        assert_eq!(bishop_pinned.pinned(), SquarePosition::new(1, 4).to_bit_board());
+        assert!(!generate_legal_moves(&bishop_pinned)
+            .iter()
+            .any(|chess_move| chess_move.active().piece_type() == PieceType::Bishop));
+
+        let rook_pinned: BoardState = "4r2k/8/8/8/8/8/4R3/4K3 w - - 0 1".parse().unwrap();
+        assert_eq!(generate_legal_moves(&rook_pinned)
+                       .iter()
+                       .filter(|chess_move| chess_move.active().piece_type() == PieceType::Rook)
+                       .count(),
+                   6);
+    }
+
+    #[test]
+    fn en_passant_discovered_check_test() {
+        let board: BoardState = "8/8/8/KPp4r/8/8/8/7k w - c6 0 1".parse().unwrap();
+
+        assert!(generate_pseudo_legal_moves(&board)
+            .iter()
+            .any(|chess_move| *chess_move.move_type() == MoveType::EnPassant));
+        assert!(!generate_legal_moves(&board)
+            .iter()
+            .any(|chess_move| *chess_move.move_type() == MoveType::EnPassant));
+    }
+
+    #[test]
+    fn pawn_attack_board_test() {
+        let attacks = |color: Color, name: &str| PAWN_ATTACK_BOARD.get_attacks(color, square(name));
+
+        assert_eq!(attacks(Color::White, "e4"), board(&["d5", "f5"]));
+        assert_eq!(attacks(Color::Black, "e4"), board(&["d3", "f3"]));
+        assert_eq!(attacks(Color::White, "a2"), board(&["b3"]));
+        assert_eq!(attacks(Color::Black, "h7"), board(&["g6"]));
+        assert_eq!(attacks(Color::White, "c8"), EMPTY);
+        assert_eq!(attacks(Color::Black, "c1"), EMPTY);
+    }
+
+    #[test]
+    fn pawn_push_board_test() {
+        let pushes = |color: Color, name: &str, occupancy: BitBoard| {
+            PAWN_ATTACK_BOARD.get_pushes(color, square(name), occupancy)
+        };
+
+        assert_eq!(pushes(Color::White, "e2", EMPTY), board(&["e3", "e4"]));
+        assert_eq!(pushes(Color::Black, "e7", EMPTY), board(&["e6", "e5"]));
+        assert_eq!(pushes(Color::White, "e3", EMPTY), board(&["e4"]));
+        assert_eq!(pushes(Color::Black, "e3", EMPTY), board(&["e2"]));
+        assert_eq!(pushes(Color::White, "e2", board(&["e4"])), board(&["e3"]));
+        assert_eq!(pushes(Color::White, "e2", board(&["e3"])), EMPTY);
+        assert_eq!(pushes(Color::Black, "a7", board(&["a6"])), EMPTY);
+        assert_eq!(pushes(Color::White, "h8", EMPTY), EMPTY);
+        assert_eq!(pushes(Color::Black, "h1", EMPTY), EMPTY);
+    }
+
+    #[test]
+    fn queen_attack_board_test() {
+        let rook = MagicAttackBoard::new(SliderType::Rook);
+        let bishop = MagicAttackBoard::new(SliderType::Bishop);
+        let occupancies = [EMPTY,
+                           FULL,
+                           BitBoard(0x00ff00000000ff00),
+                           BitBoard(0x0000182424180000)];
+
+        for square_index in 0..64 {
+            let position = SquarePosition::try_from(square_index).unwrap();
+
+            for &occupancy in occupancies.iter() {
+                assert_eq!(QUEEN_ATTACK_BOARD.get_attacks(position, occupancy),
+                           rook.get_attacks(position, occupancy) |
+                           bishop.get_attacks(position, occupancy));
+            }
+        }
+
+        assert_eq!(QUEEN_ATTACK_BOARD.get_attacks(square("d4"), EMPTY).len(), 27);
+    }
+
+    #[test]
+    fn attacks_test() {
+        let e4 = square("e4");
+        let occupancy = board(&["e5"]);
+
+        assert_eq!(attacks(Piece::new(PieceType::Pawn, Color::White), e4, occupancy),
+                   PAWN_ATTACK_BOARD.get_attacks(Color::White, e4));
+        assert_eq!(attacks(Piece::new(PieceType::Pawn, Color::Black), e4, occupancy),
+                   PAWN_ATTACK_BOARD.get_attacks(Color::Black, e4));
+        assert_eq!(attacks(Piece::new(PieceType::Knight, Color::White), e4, occupancy)
+                       .len(),
+                   8);
+        assert_eq!(attacks(Piece::new(PieceType::King, Color::Black), e4, occupancy).len(),
+                   8);
+
+        // The e5 blocker cuts the file for rooks and queens but not the diagonals.
+        let rook = attacks(Piece::new(PieceType::Rook, Color::White), e4, occupancy);
+        let bishop = attacks(Piece::new(PieceType::Bishop, Color::Black), e4, occupancy);
+        assert_eq!(rook.len(), 7 + 4);
+        assert_eq!(bishop.len(), 13);
+        assert_eq!(attacks(Piece::new(PieceType::Queen, Color::White), e4, occupancy),
+                   rook | bishop);
+    }
 }
@@ -0,0 +1,192 @@
+use std::fmt;
+
+use board_state::BoardState;
+use bit_boards::{BitBoard, EMPTY};
+use piece::{Color, PieceType};
+
+const LIGHT_SQUARES: BitBoard = BitBoard(0x55aa55aa55aa55aa);
+
+const FIFTY_MOVE_PLIES: u32 = 100;
+const SEVENTY_FIVE_MOVE_PLIES: u32 = 150;
+const THREEFOLD_PREVIOUS_OCCURRENCES: usize = 2;
+const FIVEFOLD_PREVIOUS_OCCURRENCES: usize = 4;
+
+#[derive(Debug, Copy, Hash, Clone, PartialEq, Eq)]
+pub enum GameOutcome {
+    /// The game was won by the given color.
+    Checkmate(Color),
+    Stalemate,
+    InsufficientMaterial,
+    SeventyFiveMoveRule,
+    FivefoldRepetition,
+    FiftyMoveRule,
+    ThreefoldRepetition,
+}
+
+impl GameOutcome {
+    #[inline]
+    pub fn winner(&self) -> Option<Color> {
+        match *self {
+            GameOutcome::Checkmate(winner) => Some(winner),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_draw(&self) -> bool {
+        self.winner().is_none()
+    }
+
+    /// Whether the game only ends once a player claims the draw, rather than automatically.
+    #[inline]
+    pub fn is_claimable(&self) -> bool {
+        match *self {
+            GameOutcome::FiftyMoveRule |
+            GameOutcome::ThreefoldRepetition => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for GameOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match *self {
+            GameOutcome::Checkmate(Color::White) => "white wins by checkmate",
+            GameOutcome::Checkmate(Color::Black) => "black wins by checkmate",
+            GameOutcome::Stalemate => "draw by stalemate",
+            GameOutcome::InsufficientMaterial => "draw by insufficient material",
+            GameOutcome::SeventyFiveMoveRule => "draw by the seventy-five-move rule",
+            GameOutcome::FivefoldRepetition => "draw by fivefold repetition",
+            GameOutcome::FiftyMoveRule => "draw by the fifty-move rule",
+            GameOutcome::ThreefoldRepetition => "draw by threefold repetition",
+        };
+
+        write!(f, "{}", description)
+    }
+}
+
+/// Determines whether the game is over, checking the outcomes that end the game outright before
+/// the ones that have to be claimed.
+pub fn game_outcome(board: &BoardState) -> Option<GameOutcome> {
+    if board.legal_moves().is_empty() {
+        return if !board.checkers().is_empty() {
+            Some(GameOutcome::Checkmate(!board.active_color()))
+        } else {
+            Some(GameOutcome::Stalemate)
+        };
+    }
+
+    let repetitions = board.repetition_count();
+
+    if insufficient_material(board) {
+        Some(GameOutcome::InsufficientMaterial)
+    } else if board.halfmove_clock() >= SEVENTY_FIVE_MOVE_PLIES {
+        Some(GameOutcome::SeventyFiveMoveRule)
+    } else if repetitions >= FIVEFOLD_PREVIOUS_OCCURRENCES {
+        Some(GameOutcome::FivefoldRepetition)
+    } else if board.halfmove_clock() >= FIFTY_MOVE_PLIES {
+        Some(GameOutcome::FiftyMoveRule)
+    } else if repetitions >= THREEFOLD_PREVIOUS_OCCURRENCES {
+        Some(GameOutcome::ThreefoldRepetition)
+    } else {
+        None
+    }
+}
+
+/// True when neither side can possibly checkmate: bare kings, a single minor piece, or any
+/// number of bishops that all stand on squares of the same color.
+pub fn insufficient_material(board: &BoardState) -> bool {
+    let mut knights = EMPTY;
+    let mut bishops = EMPTY;
+
+    for &color in [Color::White, Color::Black].iter() {
+        if !(board[(color, PieceType::Pawn)] | board[(color, PieceType::Rook)] |
+             board[(color, PieceType::Queen)])
+            .is_empty() {
+            return false;
+        }
+
+        knights |= board[(color, PieceType::Knight)];
+        bishops |= board[(color, PieceType::Bishop)];
+    }
+
+    match (knights.len(), bishops.len()) {
+        (0, 0) | (1, 0) | (0, 1) => true,
+        (0, _) => (bishops & LIGHT_SQUARES).is_empty() || (bishops & !LIGHT_SQUARES).is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use board_state::BoardState;
+    use outcome::GameOutcome;
+    use piece::Color;
+    use test_helpers::find_move;
+
+    fn outcome_of(fen_string: &str) -> Option<GameOutcome> {
+        fen_string.parse::<BoardState>().unwrap().outcome()
+    }
+
+    #[test]
+    fn checkmate_and_stalemate_test() {
+        assert_eq!(outcome_of("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"),
+                   Some(GameOutcome::Checkmate(Color::Black)));
+        assert_eq!(outcome_of("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+                   Some(GameOutcome::Stalemate));
+        assert_eq!(outcome_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+                   None);
+    }
+
+    #[test]
+    fn move_rule_test() {
+        assert_eq!(outcome_of("4k3/8/8/8/8/8/4P3/R3K3 w - - 100 80"),
+                   Some(GameOutcome::FiftyMoveRule));
+        assert_eq!(outcome_of("4k3/8/8/8/8/8/4P3/R3K3 w - - 150 80"),
+                   Some(GameOutcome::SeventyFiveMoveRule));
+        assert_eq!(outcome_of("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80"), None);
+    }
+
+    #[test]
+    fn insufficient_material_test() {
+        let insufficient = ["4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+                            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+                            "4k3/8/8/8/8/8/8/1N2K3 b - - 0 1",
+                            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+                            "4k3/8/3b4/8/8/8/8/2B1K3 w - - 0 1"];
+        let sufficient = ["4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1",
+                          "4k3/8/8/3b4/8/8/8/2B1K3 w - - 0 1",
+                          "4k3/8/8/8/8/8/7P/4K3 w - - 0 1",
+                          "4kn2/8/8/8/8/8/8/1N2K3 w - - 0 1"];
+
+        for fen_string in insufficient.iter() {
+            assert_eq!(outcome_of(fen_string), Some(GameOutcome::InsufficientMaterial));
+        }
+
+        for fen_string in sufficient.iter() {
+            assert_eq!(outcome_of(fen_string), None);
+        }
+    }
+
+    #[test]
+    fn repetition_test() {
+        let mut board: BoardState = "4k2n/8/8/8/8/8/4P3/1N2K3 w - - 0 1".parse().unwrap();
+        let shuffle = [("b1", "c3"), ("h8", "g6"), ("c3", "b1"), ("g6", "h8")];
+        let mut outcomes = Vec::new();
+
+        for _ in 0..4 {
+            for &(start, end) in shuffle.iter() {
+                let chess_move = find_move(&board, start, end);
+                board.make_move(&chess_move);
+            }
+
+            outcomes.push(board.outcome());
+        }
+
+        assert_eq!(outcomes,
+                   vec![None,
+                        Some(GameOutcome::ThreefoldRepetition),
+                        Some(GameOutcome::ThreefoldRepetition),
+                        Some(GameOutcome::FivefoldRepetition)]);
+    }
+}
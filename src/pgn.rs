@@ -0,0 +1,964 @@
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+use board_state::{BoardState, FenValidation};
+use chess_move::Move;
+use error_types::{FromFenError, FromStrError, PgnError};
+use piece::Color;
+use san;
+
+/// The tags every exported game starts with, in export order.
+pub const SEVEN_TAG_ROSTER: [&'static str; 7] = ["Event", "Site", "Date", "Round", "White",
+                                                  "Black", "Result"];
+
+const MAX_LINE_LENGTH: usize = 80;
+
+lazy_static! {
+    /// Games without a FEN tag start from a copy of this rather than parsing `STARTING_FEN`.
+    static ref STARTING_POSITION: BoardState = BoardState::starting_position();
+}
+
+/// The termination marker at the end of a game's movetext.
+#[derive(Debug, Copy, Hash, Clone, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unknown,
+}
+
+impl FromStr for GameResult {
+    type Err = FromStrError;
+
+    fn from_str(result_string: &str) -> Result<GameResult, FromStrError> {
+        match result_string {
+            "1-0" => Ok(GameResult::WhiteWins),
+            "0-1" => Ok(GameResult::BlackWins),
+            "1/2-1/2" => Ok(GameResult::Draw),
+            "*" => Ok(GameResult::Unknown),
+            _ => Err(FromStrError::MalformedInput("result")),
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str_repr = match *self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unknown => "*",
+        };
+
+        write!(f, "{}", str_repr)
+    }
+}
+
+/// A move in the movetext along with its annotations and the alternatives to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnMove {
+    chess_move: Move,
+    nags: Vec<u8>,
+    comments: Vec<String>,
+    variations: Vec<Variation>,
+}
+
+impl PgnMove {
+    pub fn new(chess_move: Move) -> PgnMove {
+        PgnMove {
+            chess_move: chess_move,
+            nags: Vec::new(),
+            comments: Vec::new(),
+            variations: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn chess_move(&self) -> &Move {
+        &self.chess_move
+    }
+
+    /// Numeric annotation glyphs, with the `!` and `?` suffixes converted to their glyphs.
+    #[inline]
+    pub fn nags(&self) -> &[u8] {
+        &self.nags
+    }
+
+    /// Comments following the move.
+    #[inline]
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// Variations played instead of this move.
+    #[inline]
+    pub fn variations(&self) -> &[Variation] {
+        &self.variations
+    }
+
+    #[inline]
+    pub fn add_nag(&mut self, nag: u8) {
+        self.nags.push(nag);
+    }
+
+    #[inline]
+    pub fn add_comment(&mut self, comment: String) {
+        self.comments.push(comment);
+    }
+
+    /// Adds a line played from the position before this move.
+    #[inline]
+    pub fn add_variation(&mut self, variation: Variation) {
+        self.variations.push(variation);
+    }
+}
+
+/// A line of moves, either the main line of a game or a recursive annotation variation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variation {
+    comments: Vec<String>,
+    moves: Vec<PgnMove>,
+}
+
+impl Variation {
+    pub fn new() -> Variation {
+        Variation {
+            comments: Vec::new(),
+            moves: Vec::new(),
+        }
+    }
+
+    /// Comments preceding the first move of the line.
+    #[inline]
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    #[inline]
+    pub fn moves(&self) -> &[PgnMove] {
+        &self.moves
+    }
+
+    #[inline]
+    pub fn add_comment(&mut self, comment: String) {
+        self.comments.push(comment);
+    }
+
+    /// Appends a move, which must be legal after the moves already in the line.
+    #[inline]
+    pub fn push(&mut self, pgn_move: PgnMove) {
+        self.moves.push(pgn_move);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    tags: Vec<(String, String)>,
+    main_line: Variation,
+    result: GameResult,
+}
+
+impl Game {
+    /// Creates a game with no tags or moves, to be filled in for export.
+    pub fn new() -> Game {
+        Game {
+            tags: Vec::new(),
+            main_line: Variation::new(),
+            result: GameResult::Unknown,
+        }
+    }
+
+    /// The tag pairs in the order they were read.
+    #[inline]
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|&&(ref tag_name, _)| tag_name == name)
+            .map(|&(_, ref value)| value.as_str())
+    }
+
+    #[inline]
+    pub fn event(&self) -> Option<&str> {
+        self.tag("Event")
+    }
+
+    #[inline]
+    pub fn site(&self) -> Option<&str> {
+        self.tag("Site")
+    }
+
+    #[inline]
+    pub fn date(&self) -> Option<&str> {
+        self.tag("Date")
+    }
+
+    #[inline]
+    pub fn round(&self) -> Option<&str> {
+        self.tag("Round")
+    }
+
+    #[inline]
+    pub fn white(&self) -> Option<&str> {
+        self.tag("White")
+    }
+
+    #[inline]
+    pub fn black(&self) -> Option<&str> {
+        self.tag("Black")
+    }
+
+    /// The result given by the movetext termination marker.
+    #[inline]
+    pub fn result(&self) -> GameResult {
+        self.result
+    }
+
+    #[inline]
+    pub fn set_result(&mut self, result: GameResult) {
+        self.result = result;
+    }
+
+    #[inline]
+    pub fn main_line(&self) -> &Variation {
+        &self.main_line
+    }
+
+    #[inline]
+    pub fn main_line_mut(&mut self) -> &mut Variation {
+        &mut self.main_line
+    }
+
+    /// Sets a tag, replacing the value of an existing tag with the same name.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|&&mut (ref tag_name, _)| tag_name == name) {
+            Some(&mut (_, ref mut tag_value)) => {
+                *tag_value = value.to_owned();
+                return;
+            }
+            None => {}
+        }
+
+        self.tags.push((name.to_owned(), value.to_owned()));
+    }
+
+    /// The position the movetext starts from, taken from the FEN tag when there is one.
+    pub fn starting_position(&self) -> Result<BoardState, FromFenError> {
+        starting_position(&self.tags)
+    }
+}
+
+fn starting_position(tags: &[(String, String)]) -> Result<BoardState, FromFenError> {
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|&&(ref tag_name, _)| tag_name == name)
+            .map(|&(_, ref value)| value.as_str())
+    };
+
+    let mut board = match tag("FEN") {
+        Some(fen_string) if tag("SetUp") != Some("0") => {
+            BoardState::from_fen(fen_string, FenValidation::Lenient)?
+        }
+        _ => STARTING_POSITION.clone(),
+    };
+    if let Some(variant) = tag("Variant") {
+        let variant = variant.to_lowercase();
+        if variant.contains("960") || variant.starts_with("fischerandom") {
+            board.set_chess960(true);
+        }
+    }
+
+    Ok(board)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    StartVariation,
+    EndVariation,
+    San(String),
+    Result(GameResult),
+}
+
+/// Reads games one at a time from a PGN source, so that only the game being parsed is held in
+/// memory. After an error the reader skips to the start of the next game.
+pub struct PgnReader<R: BufRead> {
+    reader: R,
+    line: Vec<char>,
+    position: usize,
+    line_number: usize,
+    peeked: Option<Token>,
+    finished: bool,
+    /// Whether the game being read has reached its result, or the tags of the next game.
+    game_ended: bool,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> PgnReader<R> {
+        PgnReader {
+            reader: reader,
+            line: Vec::new(),
+            position: 0,
+            line_number: 0,
+            peeked: None,
+            finished: false,
+            game_ended: false,
+        }
+    }
+
+    /// The number of the line the reader has reached, counting from 1.
+    #[inline]
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Reads the next line, skipping lines escaped with `%`. Invalid UTF-8 is replaced rather than
+    /// rejected, as many databases are Latin-1 encoded.
+    fn read_line(&mut self) -> Result<bool, PgnError> {
+        let mut bytes = Vec::new();
+
+        loop {
+            bytes.clear();
+            let read = match self.reader.read_until(b'\n', &mut bytes) {
+                Ok(read) => read,
+                Err(err) => {
+                    self.finished = true;
+                    return Err(PgnError::Io(err));
+                }
+            };
+
+            if read == 0 {
+                self.finished = true;
+                self.line.clear();
+                self.position = 0;
+                return Ok(false);
+            }
+
+            self.line_number += 1;
+            if bytes[0] != b'%' {
+                self.line = String::from_utf8_lossy(&bytes).chars().collect();
+                self.position = 0;
+                return Ok(true);
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, PgnError> {
+        if let Some(token) = self.peeked.take() {
+            return Ok(Some(token));
+        }
+
+        loop {
+            if self.position >= self.line.len() {
+                if self.finished || !self.read_line()? {
+                    return Ok(None);
+                }
+                continue;
+            }
+
+            let current = self.line[self.position];
+            match current {
+                '[' => return self.read_tag().map(Some),
+                '{' => return self.read_brace_comment().map(Some),
+                ';' => {
+                    let comment: String = self.line[self.position + 1..].iter().cloned().collect();
+                    self.position = self.line.len();
+                    return Ok(Some(Token::Comment(comment.trim().to_owned())));
+                }
+                '(' => {
+                    self.position += 1;
+                    return Ok(Some(Token::StartVariation));
+                }
+                ')' => {
+                    self.position += 1;
+                    return Ok(Some(Token::EndVariation));
+                }
+                '$' => {
+                    self.position += 1;
+                    let digits = self.take_while(|c| c.is_digit(10));
+                    return match digits.parse() {
+                        Ok(nag) => Ok(Some(Token::Nag(nag))),
+                        Err(_) => {
+                            Err(PgnError::MalformedMovetext(self.line_number,
+                                                            "invalid numeric annotation glyph"))
+                        }
+                    };
+                }
+                '!' | '?' => {
+                    let suffix = self.take_while(|c| c == '!' || c == '?');
+                    return match suffix_nag(&suffix) {
+                        Some(nag) => Ok(Some(Token::Nag(nag))),
+                        None => {
+                            Err(PgnError::MalformedMovetext(self.line_number,
+                                                            "invalid move suffix annotation"))
+                        }
+                    };
+                }
+                _ if current.is_whitespace() || current == '\u{feff}' => self.position += 1,
+                _ => {
+                    let symbol = self.take_while(|c| {
+                        !c.is_whitespace() && !"[]{}();$!?".contains(c)
+                    });
+
+                    if let Ok(result) = symbol.parse() {
+                        return Ok(Some(Token::Result(result)));
+                    }
+                    if symbol.starts_with("0-0") {
+                        return Ok(Some(Token::San(symbol)));
+                    }
+
+                    let san = symbol.trim_left_matches(|c: char| c.is_digit(10))
+                        .trim_left_matches('.');
+                    if !san.is_empty() {
+                        return Ok(Some(Token::San(san.to_owned())));
+                    }
+                }
+            }
+        }
+    }
+
+    fn take_while<F>(&mut self, predicate: F) -> String
+        where F: Fn(char) -> bool
+    {
+        let start = self.position;
+        while self.position < self.line.len() && predicate(self.line[self.position]) {
+            self.position += 1;
+        }
+
+        self.line[start..self.position].iter().cloned().collect()
+    }
+
+    fn read_tag(&mut self) -> Result<Token, PgnError> {
+        let line_number = self.line_number;
+        let malformed = |reader: &mut PgnReader<R>| {
+            reader.position = reader.line.len();
+            Err(PgnError::MalformedTag(line_number))
+        };
+
+        self.position += 1;
+        self.take_while(char::is_whitespace);
+        let name = self.take_while(|c| c.is_alphanumeric() || c == '_');
+        self.take_while(char::is_whitespace);
+
+        if name.is_empty() || self.line.get(self.position) != Some(&'"') {
+            return malformed(self);
+        }
+        self.position += 1;
+
+        let mut value = String::new();
+        loop {
+            match self.line.get(self.position).cloned() {
+                Some('"') => break,
+                Some('\\') if self.position + 1 < self.line.len() => {
+                    value.push(self.line[self.position + 1]);
+                    self.position += 2;
+                }
+                Some(c) if c != '\n' && c != '\r' => {
+                    value.push(c);
+                    self.position += 1;
+                }
+                _ => return malformed(self),
+            }
+        }
+        self.position += 1;
+
+        self.take_while(char::is_whitespace);
+        if self.line.get(self.position) != Some(&']') {
+            return malformed(self);
+        }
+        self.position += 1;
+
+        Ok(Token::Tag(name, value))
+    }
+
+    fn read_brace_comment(&mut self) -> Result<Token, PgnError> {
+        let line_number = self.line_number;
+        let mut comment = String::new();
+        self.position += 1;
+
+        loop {
+            let text = self.take_while(|c| c != '}');
+            comment += &text;
+
+            if self.position < self.line.len() {
+                self.position += 1;
+                let words: Vec<&str> = comment.split_whitespace().collect();
+                return Ok(Token::Comment(words.join(" ")));
+            }
+
+            if !self.read_line()? {
+                return Err(PgnError::UnterminatedComment(line_number));
+            }
+        }
+    }
+
+    fn read_game(&mut self) -> Result<Option<Game>, PgnError> {
+        self.game_ended = false;
+        let mut tags = Vec::new();
+        loop {
+            match self.next_token()? {
+                Some(Token::Tag(name, value)) => tags.push((name, value)),
+                Some(token) => {
+                    self.peeked = Some(token);
+                    break;
+                }
+                None if tags.is_empty() => return Ok(None),
+                None => break,
+            }
+        }
+
+        let mut board = starting_position(&tags)?;
+        let mut lines = vec![Variation::new()];
+        let mut result = GameResult::Unknown;
+
+        loop {
+            let token = match self.next_token()? {
+                Some(token) => token,
+                None => break,
+            };
+
+            match token {
+                Token::Tag(_, _) => {
+                    self.peeked = Some(token);
+                    break;
+                }
+                Token::Result(game_result) => {
+                    result = game_result;
+                    break;
+                }
+                Token::San(san_string) => {
+                    let chess_move = san::parse_san(&board, &san_string)
+                        .map_err(|err| PgnError::IllegalMove(self.line_number, err))?;
+                    board.make_move(&chess_move);
+                    lines.last_mut().unwrap().moves.push(PgnMove::new(chess_move));
+                }
+                Token::Nag(nag) => {
+                    if let Some(pgn_move) = lines.last_mut().unwrap().moves.last_mut() {
+                        pgn_move.nags.push(nag);
+                    }
+                }
+                Token::Comment(comment) => {
+                    let line = lines.last_mut().unwrap();
+                    match line.moves.last_mut() {
+                        Some(pgn_move) => pgn_move.comments.push(comment),
+                        None => line.comments.push(comment),
+                    }
+                }
+                Token::StartVariation => {
+                    let replaced = match lines.last().unwrap().moves.last() {
+                        Some(pgn_move) => pgn_move.chess_move,
+                        None => {
+                            return Err(PgnError::MalformedMovetext(self.line_number,
+                                                                   "variation has no move to \
+                                                                    replace"))
+                        }
+                    };
+                    board.unmake_move(&replaced);
+                    lines.push(Variation::new());
+                }
+                Token::EndVariation => {
+                    if lines.len() == 1 {
+                        return Err(PgnError::MalformedMovetext(self.line_number,
+                                                               "unmatched variation end"));
+                    }
+
+                    let variation = lines.pop().unwrap();
+                    for pgn_move in variation.moves.iter().rev() {
+                        board.unmake_move(&pgn_move.chess_move);
+                    }
+
+                    let replaced = lines.last_mut().unwrap().moves.last_mut().unwrap();
+                    board.make_move(&replaced.chess_move);
+                    replaced.variations.push(variation);
+                }
+            }
+        }
+
+        self.game_ended = true;
+        if lines.len() != 1 {
+            return Err(PgnError::MalformedMovetext(self.line_number, "unterminated variation"));
+        }
+
+        Ok(Some(Game {
+            tags: tags,
+            main_line: lines.pop().unwrap(),
+            result: result,
+        }))
+    }
+
+    /// Discards tokens up to the result of the current game. Tags are discarded as well, since an
+    /// error in the middle of a game's header leaves the rest of that header still to be read.
+    fn skip_game(&mut self) {
+        if self.game_ended {
+            return;
+        }
+
+        loop {
+            match self.next_token() {
+                Ok(Some(Token::Result(_))) |
+                Ok(None) => return,
+                Ok(Some(_)) => {}
+                Err(_) if self.finished => return,
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Result<Game, PgnError>> {
+        match self.read_game() {
+            Ok(Some(game)) => Some(Ok(game)),
+            Ok(None) => None,
+            Err(err) => {
+                self.skip_game();
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Writes games in the PGN export format.
+pub struct PgnWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> PgnWriter<W> {
+    pub fn new(writer: W) -> PgnWriter<W> {
+        PgnWriter { writer: writer }
+    }
+
+    /// Writes the seven tag roster followed by any other tags, then the movetext wrapped at 80
+    /// columns. Missing roster tags are written as unknown, and the Result tag always matches
+    /// the game's result.
+    pub fn write_game(&mut self, game: &Game) -> Result<(), PgnError> {
+        for &name in SEVEN_TAG_ROSTER.iter() {
+            let value = match name {
+                "Result" => game.result.to_string(),
+                "Date" => game.tag(name).unwrap_or("????.??.??").to_owned(),
+                _ => game.tag(name).unwrap_or("?").to_owned(),
+            };
+            self.write_tag(name, &value)?;
+        }
+
+        for &(ref name, ref value) in game.tags.iter() {
+            if !SEVEN_TAG_ROSTER.contains(&name.as_str()) {
+                self.write_tag(name, value)?;
+            }
+        }
+        writeln!(self.writer, "")?;
+
+        let mut board = game.starting_position()?;
+        let mut tokens = Vec::new();
+        movetext_tokens(&mut board, &game.main_line, &mut tokens);
+        tokens.push(game.result.to_string());
+
+        let mut line = String::new();
+        let mut line_length = 0;
+        for token in tokens {
+            let token_length = token.chars().count();
+
+            if line_length > 0 && line_length + 1 + token_length > MAX_LINE_LENGTH {
+                writeln!(self.writer, "{}", line)?;
+                line.clear();
+                line_length = 0;
+            }
+
+            if line_length > 0 {
+                line.push(' ');
+                line_length += 1;
+            }
+            line += &token;
+            line_length += token_length;
+        }
+        writeln!(self.writer, "{}", line)?;
+        writeln!(self.writer, "")?;
+
+        Ok(())
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_tag(&mut self, name: &str, value: &str) -> Result<(), PgnError> {
+        let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+        writeln!(self.writer, "[{} \"{}\"]", name, escaped)?;
+
+        Ok(())
+    }
+}
+
+/// Appends the movetext of `variation` to `tokens`, one token per word so that the caller can
+/// wrap lines between any two tokens. `board` is left as it was found.
+fn movetext_tokens(board: &mut BoardState, variation: &Variation, tokens: &mut Vec<String>) {
+    for comment in variation.comments.iter() {
+        comment_tokens(comment, tokens);
+    }
+
+    let mut needs_number = true;
+    for pgn_move in variation.moves.iter() {
+        if board.active_color() == Color::White {
+            tokens.push(format!("{}.", board.fullmove_clock()));
+        } else if needs_number {
+            tokens.push(format!("{}...", board.fullmove_clock()));
+        }
+
+        tokens.push(san::format_san(board, &pgn_move.chess_move));
+
+        for nag in pgn_move.nags.iter() {
+            tokens.push(format!("${}", nag));
+        }
+
+        for comment in pgn_move.comments.iter() {
+            comment_tokens(comment, tokens);
+        }
+
+        for alternative in pgn_move.variations.iter() {
+            let first = tokens.len();
+            movetext_tokens(board, alternative, tokens);
+
+            if tokens.len() == first {
+                tokens.push("()".to_owned());
+            } else {
+                tokens[first].insert(0, '(');
+                tokens.last_mut().unwrap().push(')');
+            }
+        }
+
+        needs_number = !pgn_move.comments.is_empty() || !pgn_move.variations.is_empty();
+        board.make_move(&pgn_move.chess_move);
+    }
+
+    for pgn_move in variation.moves.iter().rev() {
+        board.unmake_move(&pgn_move.chess_move);
+    }
+}
+
+fn comment_tokens(comment: &str, tokens: &mut Vec<String>) {
+    let first = tokens.len();
+    tokens.extend(comment.split_whitespace().map(|word| word.to_owned()));
+
+    if tokens.len() == first {
+        tokens.push("{}".to_owned());
+    } else {
+        tokens[first].insert(0, '{');
+        tokens.last_mut().unwrap().push('}');
+    }
+}
+
+/// Maps a `!`/`?` move suffix to its numeric annotation glyph.
+fn suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use board_state::BoardState;
+    use error_types::PgnError;
+    use pgn::{Game, GameResult, PgnMove, PgnReader, PgnWriter, Variation};
+    use san;
+    use uci;
+
+    const GAMES: &'static str = r#"
+[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+[Annotator "Anonymous \"A\""]
+
+{Opening comment} 1. e4 e5 2. Nf3 Nc6 3. Bb5 {This opening is called the Ruy Lopez.} 3... a6
+4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7
+11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15. Nb1 h6 16. Bh4 c5 17. dxe5
+Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21. Nc4 Nxc4 22. Bxc4 Nb6
+23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7 27. Qe3 Qg5 28. Qxg5
+hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5 33. f3 Bc8 34. Kf2 Bf5
+35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3 39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6
+Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+
+% an escaped line that is not part of any game
+[Event "Annotated"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+
+1. e4! (1. e3 $6 Kd7 (1... Ke7 2. Kd2) 2. Kd2) 1... Kd7?! ; rest of line comment
+2. Kd2 *
+"#;
+
+    #[test]
+    fn seven_tag_roster_test() {
+        let games: Vec<_> = PgnReader::new(GAMES.as_bytes()).collect();
+        assert_eq!(games.len(), 2);
+
+        let game = games[0].as_ref().unwrap();
+        assert_eq!(game.event(), Some("F/S Return Match"));
+        assert_eq!(game.site(), Some("Belgrade, Serbia JUG"));
+        assert_eq!(game.date(), Some("1992.11.04"));
+        assert_eq!(game.round(), Some("29"));
+        assert_eq!(game.white(), Some("Fischer, Robert J."));
+        assert_eq!(game.black(), Some("Spassky, Boris V."));
+        assert_eq!(game.tag("Result"), Some("1/2-1/2"));
+        assert_eq!(game.tag("Annotator"), Some("Anonymous \"A\""));
+        assert_eq!(game.result(), GameResult::Draw);
+
+        let main_line = game.main_line();
+        assert_eq!(main_line.moves().len(), 85);
+        assert_eq!(main_line.comments(), ["Opening comment".to_owned()]);
+        assert_eq!(main_line.moves()[4].comments(),
+                   ["This opening is called the Ruy Lopez.".to_owned()]);
+
+        let mut board = game.starting_position().unwrap();
+        for pgn_move in main_line.moves() {
+            board.make_move(pgn_move.chess_move());
+        }
+        assert_eq!(board.to_string(), "8/8/4R1p1/2k3p1/1p4P1/1P1b1P2/3K1n2/8 b - - 2 43");
+    }
+
+    #[test]
+    fn variations_and_annotations_test() {
+        let game = PgnReader::new(GAMES.as_bytes()).nth(1).unwrap().unwrap();
+        let board = game.starting_position().unwrap();
+        assert_eq!(game.result(), GameResult::Unknown);
+
+        let moves = game.main_line().moves();
+        assert_eq!(moves.len(), 3);
+        assert_eq!(uci::format_uci(&board, moves[0].chess_move()), "e2e4");
+        assert_eq!(moves[0].nags(), [1]);
+        assert_eq!(moves[1].nags(), [6]);
+        assert_eq!(moves[1].comments(), ["rest of line comment".to_owned()]);
+
+        let variation = &moves[0].variations()[0];
+        assert_eq!(variation.moves().len(), 3);
+        assert_eq!(variation.moves()[0].nags(), [6]);
+        assert_eq!(uci::format_uci(&board, variation.moves()[0].chess_move()),
+                   "e2e3");
+
+        let nested = &variation.moves()[1].variations()[0];
+        assert_eq!(nested.moves().len(), 2);
+        assert_eq!(nested.moves()[0].chess_move().end().to_string(), "e7");
+    }
+
+    #[test]
+    fn error_recovery_test() {
+        let pgn = "[Event \"Broken\"]\n\n1. e4 e5 2. Ke3 Nc6 1-0\n\n[Event \"Fine\"]\n\n1. d4 \
+                   0-1\n\n[Event \"Open\"]\n\n1. d4 (1. e4 1-0\n";
+        let mut reader = PgnReader::new(pgn.as_bytes());
+
+        match reader.next() {
+            Some(Err(PgnError::IllegalMove(3, _))) => {}
+            other => panic!("expected an illegal move error, found {:?}", other),
+        }
+
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(game.event(), Some("Fine"));
+        assert_eq!(game.result(), GameResult::BlackWins);
+
+        match reader.next() {
+            Some(Err(PgnError::MalformedMovetext(_, _))) => {}
+            other => panic!("expected a movetext error, found {:?}", other),
+        }
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn malformed_tag_recovery_test() {
+        let pgn = "[Event \"Broken\"]\n[Site \"Nowhere]\n[Round \"1\"]\n\n1. e4 e5 1-0\n\n\
+                   [Event \"Fine\"]\n\n1. d4 d5 1/2-1/2\n";
+        let mut reader = PgnReader::new(pgn.as_bytes());
+
+        match reader.next() {
+            Some(Err(PgnError::MalformedTag(2))) => {}
+            other => panic!("expected a malformed tag error, found {:?}", other),
+        }
+
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(game.event(), Some("Fine"));
+        assert_eq!(game.result(), GameResult::Draw);
+        assert!(reader.next().is_none());
+    }
+
+    fn write_games(games: &[Game]) -> String {
+        let mut writer = PgnWriter::new(Vec::new());
+        for game in games {
+            writer.write_game(game).unwrap();
+        }
+
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn write_game_test() {
+        let mut board: BoardState = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1".parse().unwrap();
+        let mut game = Game::new();
+        game.set_tag("White", "Engine \\ \"One\"");
+        game.set_tag("SetUp", "1");
+        game.set_tag("FEN", &board.to_string());
+        game.set_result(GameResult::Draw);
+
+        let mut alternative = Variation::new();
+        alternative.add_comment("Also drawn".to_owned());
+        alternative.push(PgnMove::new(san::parse_san(&board, "Kf7").unwrap()));
+
+        let mut first = PgnMove::new(san::parse_san(&board, "Kd7").unwrap());
+        first.add_nag(10);
+        first.add_variation(alternative);
+        board.make_move(first.chess_move());
+        game.main_line_mut().push(first);
+
+        let mut second = PgnMove::new(san::parse_san(&board, "e4").unwrap());
+        second.add_comment("The only   try".to_owned());
+        game.main_line_mut().push(second);
+
+        assert_eq!(write_games(&[game]),
+                   "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+                    [White \"Engine \\\\ \\\"One\\\"\"]\n[Black \"?\"]\n[Result \"1/2-1/2\"]\n\
+                    [SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 1\"]\n\n1... Kd7 $10 \
+                    ({Also drawn} 1... Kf7) 2. e4 {The only try} 1/2-1/2\n\n");
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let games: Vec<Game> = PgnReader::new(GAMES.as_bytes())
+            .map(|game| game.unwrap())
+            .collect();
+        let written = write_games(&games);
+
+        for line in written.lines() {
+            assert!(line.chars().count() <= 80, "{}", line);
+        }
+
+        let reread: Vec<Game> = PgnReader::new(written.as_bytes())
+            .map(|game| game.unwrap())
+            .collect();
+        assert_eq!(reread.len(), games.len());
+        assert_eq!(reread[0], games[0]);
+
+        for (game, reread_game) in games.iter().zip(reread.iter()) {
+            assert_eq!(reread_game.main_line(), game.main_line());
+            assert_eq!(reread_game.result(), game.result());
+            assert_eq!(reread_game.tag("FEN"), game.tag("FEN"));
+        }
+
+        assert_eq!(write_games(&reread), written);
+    }
+}
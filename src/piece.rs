@@ -83,6 +83,52 @@ pub enum PromotionType {
     Rook,
 }
 
+impl PromotionType {
+    #[inline]
+    pub fn piece_type(&self) -> PieceType {
+        match *self {
+            PromotionType::Queen => PieceType::Queen,
+            PromotionType::Bishop => PieceType::Bishop,
+            PromotionType::Knight => PieceType::Knight,
+            PromotionType::Rook => PieceType::Rook,
+        }
+    }
+}
+
+/// The pieces whose attacks slide along their lines until blocked, and so need the occupancy.
+#[derive(Debug, Copy, Hash, Clone, PartialEq, Eq)]
+pub enum SliderType {
+    Rook,
+    Bishop,
+}
+
+impl SliderType {
+    #[inline]
+    pub fn piece_type(&self) -> PieceType {
+        match *self {
+            SliderType::Rook => PieceType::Rook,
+            SliderType::Bishop => PieceType::Bishop,
+        }
+    }
+}
+
+/// The pieces whose attacks are a fixed set of steps from their square.
+#[derive(Debug, Copy, Hash, Clone, PartialEq, Eq)]
+pub enum StepperType {
+    King,
+    Knight,
+}
+
+impl StepperType {
+    #[inline]
+    pub fn piece_type(&self) -> PieceType {
+        match *self {
+            StepperType::King => PieceType::King,
+            StepperType::Knight => PieceType::Knight,
+        }
+    }
+}
+
 impl PieceType {
     #[inline]
     pub fn value(&self) -> u32 {
@@ -97,7 +143,7 @@ impl PieceType {
     }
 }
 
-#[derive(Debug, Copy, Hash, Clone)]
+#[derive(Debug, Copy, Hash, Clone, PartialEq, Eq)]
 pub struct Piece {
     piece_type: PieceType,
     color: Color,
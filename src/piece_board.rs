@@ -76,11 +76,6 @@ impl FromStr for PieceBoard {
                             let board_index = file_index + rank_index * 8;
                             piece_board.board[board_index] = Some(piece_to_insert);
 
-                            println!("Inserted: {} at {} based on {}",
-                                     piece_to_insert,
-                                     board_index,
-                                     x);
-
                             file_index += 1;
                         }
                     }
@@ -0,0 +1,324 @@
+use board_state::BoardState;
+use castling::CastleType;
+use chess_move::{Move, MoveType};
+use error_types::SanError;
+use piece::{PieceType, PromotionType};
+use square_position::SquarePosition;
+
+/// Formats `chess_move` in Standard Algebraic Notation. The move must be legal in `board`, which
+/// is temporarily played into to find the check and checkmate suffixes.
+pub fn format_san(board: &mut BoardState, chess_move: &Move) -> String {
+    let mut san = match *chess_move.move_type() {
+        MoveType::Castle(CastleType::Kingside) => "O-O".to_owned(),
+        MoveType::Castle(CastleType::Queenside) => "O-O-O".to_owned(),
+        _ => format_san_body(board, chess_move),
+    };
+
+    board.make_move(chess_move);
+    if !board.checkers().is_empty() {
+        if board.legal_moves().is_empty() {
+            san.push('#');
+        } else {
+            san.push('+');
+        }
+    }
+    board.unmake_move(chess_move);
+
+    san
+}
+
+fn format_san_body(board: &BoardState, chess_move: &Move) -> String {
+    let piece_type = chess_move.active().piece_type();
+    let start = *chess_move.start();
+    let is_capture = match *chess_move.move_type() {
+        MoveType::Capture(_) |
+        MoveType::CapturePromotion(_, _) |
+        MoveType::EnPassant => true,
+        _ => false,
+    };
+
+    let mut san = String::new();
+
+    if piece_type == PieceType::Pawn {
+        if is_capture {
+            san.push(file_char(start.file));
+        }
+    } else {
+        san += &piece_type.to_string();
+
+        let rivals: Vec<SquarePosition> = board.legal_moves()
+            .into_iter()
+            .filter(|other| {
+                other.active().piece_type() == piece_type && other.end() == chess_move.end() &&
+                *other.start() != start
+            })
+            .map(|other| *other.start())
+            .collect();
+
+        if !rivals.is_empty() {
+            let shares_file = rivals.iter().any(|rival| rival.file == start.file);
+            let shares_rank = rivals.iter().any(|rival| rival.rank == start.rank);
+
+            if !shares_file {
+                san.push(file_char(start.file));
+            } else if !shares_rank {
+                san.push(rank_char(start.rank));
+            } else {
+                san += &start.to_string();
+            }
+        }
+    }
+
+    if is_capture {
+        san.push('x');
+    }
+
+    san += &chess_move.end().to_string();
+
+    match *chess_move.move_type() {
+        MoveType::Promotion(promotion) |
+        MoveType::CapturePromotion(promotion, _) => {
+            san.push('=');
+            san += &promotion.piece_type().to_string();
+        }
+        _ => {}
+    }
+
+    san
+}
+
+/// Resolves a move in Standard Algebraic Notation against the legal moves of `board`. Check,
+/// checkmate and annotation suffixes are accepted but not verified.
+pub fn parse_san(board: &BoardState, san: &str) -> Result<Move, SanError> {
+    let trimmed = san.trim_right_matches(|c| c == '+' || c == '#' || c == '!' || c == '?');
+
+    let castle_type = match trimmed {
+        "O-O" | "0-0" => Some(CastleType::Kingside),
+        "O-O-O" | "0-0-0" => Some(CastleType::Queenside),
+        _ => None,
+    };
+
+    let candidates: Vec<Move> = match castle_type {
+        Some(castle_type) => {
+            board.legal_moves()
+                .into_iter()
+                .filter(|chess_move| *chess_move.move_type() == MoveType::Castle(castle_type))
+                .collect()
+        }
+        None => {
+            let pattern = SanPattern::parse(trimmed)
+                .ok_or_else(|| SanError::MalformedInput(san.to_owned()))?;
+
+            board.legal_moves()
+                .into_iter()
+                .filter(|chess_move| pattern.matches(chess_move))
+                .collect()
+        }
+    };
+
+    match candidates.len() {
+        0 => Err(SanError::IllegalMove(san.to_owned())),
+        1 => Ok(candidates[0]),
+        _ => Err(SanError::AmbiguousMove(san.to_owned())),
+    }
+}
+
+struct SanPattern {
+    piece_type: PieceType,
+    start_file: Option<usize>,
+    start_rank: Option<usize>,
+    end: SquarePosition,
+    promotion: Option<PromotionType>,
+}
+
+impl SanPattern {
+    fn parse(san: &str) -> Option<SanPattern> {
+        let mut chars: Vec<char> = san.chars().collect();
+
+        let promotion = match chars.last().cloned().and_then(promotion_from_char) {
+            Some(promotion) => {
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+                Some(promotion)
+            }
+            None => None,
+        };
+
+        if chars.len() < 2 {
+            return None;
+        }
+
+        let end_string: String = chars[chars.len() - 2..].iter().cloned().collect();
+        let end: SquarePosition = match end_string.parse() {
+            Ok(end) => end,
+            Err(_) => return None,
+        };
+        chars.truncate(chars.len() - 2);
+
+        let piece_type = match chars.first().cloned() {
+            Some('K') => PieceType::King,
+            Some('Q') => PieceType::Queen,
+            Some('R') => PieceType::Rook,
+            Some('B') => PieceType::Bishop,
+            Some('N') => PieceType::Knight,
+            _ => PieceType::Pawn,
+        };
+        if piece_type != PieceType::Pawn {
+            chars.remove(0);
+        }
+
+        let mut pattern = SanPattern {
+            piece_type: piece_type,
+            start_file: None,
+            start_rank: None,
+            end: end,
+            promotion: promotion,
+        };
+
+        for disambiguation in chars.into_iter().filter(|&c| c != 'x' && c != ':') {
+            match disambiguation {
+                'a'...'h' => pattern.start_file = Some(disambiguation as usize - 'a' as usize),
+                '1'...'8' => pattern.start_rank = Some(disambiguation as usize - '1' as usize),
+                _ => return None,
+            }
+        }
+
+        Some(pattern)
+    }
+
+    fn matches(&self, chess_move: &Move) -> bool {
+        let promotion = match *chess_move.move_type() {
+            MoveType::Promotion(promotion) |
+            MoveType::CapturePromotion(promotion, _) => Some(promotion),
+            _ => None,
+        };
+
+        chess_move.active().piece_type() == self.piece_type && *chess_move.end() == self.end &&
+        promotion == self.promotion &&
+        self.start_file.map_or(true, |file| chess_move.start().file == file) &&
+        self.start_rank.map_or(true, |rank| chess_move.start().rank == rank)
+    }
+}
+
+fn promotion_from_char(promotion_char: char) -> Option<PromotionType> {
+    match promotion_char {
+        'Q' => Some(PromotionType::Queen),
+        'R' => Some(PromotionType::Rook),
+        'B' => Some(PromotionType::Bishop),
+        'N' => Some(PromotionType::Knight),
+        _ => None,
+    }
+}
+
+#[inline]
+fn file_char(file: usize) -> char {
+    (b'a' + file as u8) as char
+}
+
+#[inline]
+fn rank_char(rank: usize) -> char {
+    (b'1' + rank as u8) as char
+}
+
+#[cfg(test)]
+mod test {
+    use board_state::BoardState;
+    use error_types::SanError;
+    use san::{format_san, parse_san};
+
+    fn formatted_moves(fen_string: &str) -> Vec<String> {
+        let mut board: BoardState = fen_string.parse().unwrap();
+        let mut formatted: Vec<String> = board.legal_moves()
+            .iter()
+            .map(|chess_move| format_san(&mut board, chess_move))
+            .collect();
+        formatted.sort();
+
+        formatted
+    }
+
+    #[test]
+    fn disambiguation_test() {
+        let formatted = formatted_moves("1k6/8/8/8/R6R/8/8/RK6 w - - 0 1");
+
+        assert!(formatted.contains(&"Rhd4".to_owned()));
+        assert!(formatted.contains(&"R1a2".to_owned()));
+        assert!(formatted.contains(&"R4a3".to_owned()));
+        assert!(formatted.contains(&"Rh8+".to_owned()));
+        assert!(formatted.contains(&"Kb2".to_owned()));
+
+        let formatted = formatted_moves("2k5/8/8/8/4Q2Q/8/8/K6Q w - - 0 1");
+        assert!(formatted.contains(&"Qh4e1".to_owned()));
+    }
+
+    #[test]
+    fn special_moves_test() {
+        let promotions = formatted_moves("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        assert!(promotions.contains(&"b8=Q+".to_owned()));
+        assert!(promotions.contains(&"b8=N".to_owned()));
+
+        let castles = formatted_moves("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        assert!(castles.contains(&"O-O".to_owned()));
+        assert!(castles.contains(&"O-O-O".to_owned()));
+
+        let en_passant = formatted_moves("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        assert!(en_passant.contains(&"exd6".to_owned()));
+
+        let mate = formatted_moves("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        assert!(mate.contains(&"Ra8#".to_owned()));
+    }
+
+    #[test]
+    fn parse_san_test() {
+        let board: BoardState = "r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2NP1N2/PPP2PPP/R1BQKB1R b KQkq - 0 4"
+            .parse()
+            .unwrap();
+
+        let knight_move = parse_san(&board, "Nd4").unwrap();
+        assert_eq!(knight_move.start().to_string(), "c6");
+
+        let bishop_move = parse_san(&board, "Bb4").unwrap();
+        assert_eq!(bishop_move.end().to_string(), "b4");
+
+        match parse_san(&board, "Nxe4!?") {
+            Ok(chess_move) => assert_eq!(chess_move.start().to_string(), "f6"),
+            Err(err) => panic!("{}", err),
+        }
+
+        match parse_san(&board, "Qh4") {
+            Ok(_) => panic!("expected Qh4 to be illegal"),
+            Err(SanError::IllegalMove(_)) => {}
+            Err(err) => panic!("{}", err),
+        }
+
+        let rooks: BoardState = "1k6/8/8/8/R6R/8/8/RK6 w - - 0 1".parse().unwrap();
+        match parse_san(&rooks, "Rd4") {
+            Err(SanError::AmbiguousMove(_)) => {}
+            _ => panic!("expected Rd4 to be ambiguous"),
+        }
+        assert_eq!(parse_san(&rooks, "Rhd4").unwrap().start().to_string(), "h4");
+
+        match parse_san(&board, "Zz9") {
+            Err(SanError::MalformedInput(_)) => {}
+            _ => panic!("expected Zz9 to be malformed"),
+        }
+    }
+
+    #[test]
+    fn san_round_trip_test() {
+        let fen_strings = ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                           "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                           "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"];
+
+        for fen_string in fen_strings.iter() {
+            let mut board: BoardState = fen_string.parse().unwrap();
+
+            for chess_move in board.legal_moves() {
+                let san = format_san(&mut board, &chess_move);
+                assert_eq!(parse_san(&board, &san).unwrap(), chess_move, "{}", san);
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::cmp;
+use std::convert::TryFrom;
+
+use bit_boards::{BitBoard, bit_scan_forward};
+use board_state::BoardState;
+use chess_move::{Move, MoveType};
+use piece::{Color, PieceType};
+use square_position::SquarePosition;
+
+const EXCHANGE_ORDER: [PieceType; 6] = [PieceType::Pawn,
+                                        PieceType::Knight,
+                                        PieceType::Bishop,
+                                        PieceType::Rook,
+                                        PieceType::Queen,
+                                        PieceType::King];
+
+/// Static exchange evaluation of `chess_move`: the material the side to move gains once every
+/// recapture on the destination square has been played out, each side always recapturing with
+/// its least valuable attacker and stopping when continuing would lose material. Sliders behind
+/// an attacker join the exchange once it has moved. Pins are not considered.
+pub fn see(board: &BoardState, chess_move: &Move) -> i32 {
+    let color = chess_move.active().color();
+    let start = *chess_move.start();
+    let end = *chess_move.end();
+
+    let mut occupancy = board.occupancy() & !start.to_bit_board();
+    let mut on_square = chess_move.active().value() as i32;
+
+    let first_gain = match *chess_move.move_type() {
+        MoveType::Capture(captured) => captured.value() as i32,
+        MoveType::CapturePromotion(promotion, captured) => {
+            on_square = promotion.piece_type().value() as i32;
+            captured.value() as i32 + on_square - PieceType::Pawn.value() as i32
+        }
+        MoveType::Promotion(promotion) => {
+            on_square = promotion.piece_type().value() as i32;
+            on_square - PieceType::Pawn.value() as i32
+        }
+        MoveType::EnPassant => {
+            occupancy &= !SquarePosition::new(start.rank, end.file).to_bit_board();
+            PieceType::Pawn.value() as i32
+        }
+        _ => 0,
+    };
+
+    let mut gains = vec![first_gain];
+    let mut side = !color;
+
+    loop {
+        let attackers = board.attackers_to(end, occupancy) & board[side];
+        let (attacker, attacker_type) = match least_valuable_attacker(board, attackers, side) {
+            Some(least_valuable) => least_valuable,
+            None => break,
+        };
+
+        if attacker_type == PieceType::King &&
+           !(board.attackers_to(end, occupancy & !attacker) & board[!side]).is_empty() {
+            break;
+        }
+
+        let mut gain = on_square - *gains.last().unwrap();
+        on_square = attacker_type.value() as i32;
+
+        if attacker_type == PieceType::Pawn && (end.rank == 0 || end.rank == 7) {
+            gain += PieceType::Queen.value() as i32 - on_square;
+            on_square = PieceType::Queen.value() as i32;
+        }
+
+        gains.push(gain);
+        occupancy &= !attacker;
+        side = !side;
+    }
+
+    while gains.len() > 1 {
+        let reply = gains.pop().unwrap();
+        let previous = gains.last_mut().unwrap();
+        *previous = -cmp::max(-*previous, reply);
+    }
+
+    gains[0]
+}
+
+fn least_valuable_attacker(board: &BoardState,
+                           attackers: BitBoard,
+                           color: Color)
+                           -> Option<(BitBoard, PieceType)> {
+    for &piece_type in EXCHANGE_ORDER.iter() {
+        let candidates = attackers & board[(color, piece_type)];
+
+        if let Some(index) = bit_scan_forward(candidates) {
+            let position = SquarePosition::try_from(index).unwrap();
+            return Some((position.to_bit_board(), piece_type));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use board_state::BoardState;
+    use san;
+    use see::see;
+
+    fn see_san(fen_string: &str, san_string: &str) -> i32 {
+        let board: BoardState = fen_string.parse().unwrap();
+        let chess_move = san::parse_san(&board, san_string).unwrap();
+
+        see(&board, &chess_move)
+    }
+
+    #[test]
+    fn simple_exchange_test() {
+        assert_eq!(see_san("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "Rxe5"),
+                   100);
+        assert_eq!(see_san("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                           "Nxe5"),
+                   100 - 325);
+        assert_eq!(see_san("4k3/8/3p4/4p3/8/8/8/4RK2 w - - 0 1", "Rxe5"), 100 - 500);
+        assert_eq!(see_san("4k3/8/8/4p3/8/8/8/4RK2 w - - 0 1", "Re2"), 0);
+    }
+
+    #[test]
+    fn x_ray_test() {
+        // The queen behind the rook recaptures once the rook has been exchanged.
+        assert_eq!(see_san("4k3/4r3/8/4p3/8/8/4R3/4Q1K1 w - - 0 1", "Rxe5"), 100);
+        assert_eq!(see_san("4k3/4r3/8/4p3/8/8/8/4RK2 w - - 0 1", "Rxe5"), 100 - 500);
+        // Black's doubled rooks outlast the rook and queen battery.
+        assert_eq!(see_san("3rk3/3r4/8/3p4/8/8/3R4/3Q2K1 w - - 0 1", "Rxd5"), 100 - 500);
+    }
+
+    #[test]
+    fn special_moves_test() {
+        assert_eq!(see_san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6"), 100);
+        assert_eq!(see_san("3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1", "cxd8=Q+"), 500 - 100);
+        assert_eq!(see_san("1r2k3/2P5/8/8/8/8/8/4K3 w - - 0 1", "c8=Q+"), -100);
+        assert_eq!(see_san("8/5k2/4p3/8/8/8/B7/5K2 w - - 0 1", "Bxe6+"), 100 - 325);
+        // The king cannot recapture on a defended square.
+        assert_eq!(see_san("8/5k2/4p3/8/8/8/B7/4RK2 w - - 0 1", "Bxe6+"), 100);
+    }
+}
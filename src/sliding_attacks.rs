@@ -0,0 +1,364 @@
+//! Alternatives to the magic bitboard sliding attack generator. Move generation uses whichever one
+//! the `kogge-stone`, `hyperbola-quintessence` or `pext` cargo feature selects (see
+//! `move_gen::SlidingAttackBoard`), but all of them are always built so they can be benchmarked
+//! and checked against each other.
+
+use std::convert::TryFrom;
+
+use bit_boards::{BitBoard, EMPTY};
+use move_gen::{BitboardGenerator, generate_mask, generate_sliding_attack_bitboards};
+use piece::{PieceType, SliderType};
+use square_position::{CARDINAL, Direction, INTERMEDIATE, NORTH, SOUTH, SquarePosition};
+
+const NOT_A_FILE: u64 = 0xfefefefefefefefe;
+const NOT_H_FILE: u64 = 0x7f7f7f7f7f7f7f7f;
+
+/// Shift amounts and wrap masks for the rook rays: north, south, east and west.
+static ROOK_SHIFTS: [(i32, u64); 4] = [(8, !0), (-8, !0), (1, NOT_A_FILE), (-1, NOT_H_FILE)];
+/// Shift amounts and wrap masks for the bishop rays: north east, north west, south east and south
+/// west.
+static BISHOP_SHIFTS: [(i32, u64); 4] =
+    [(9, NOT_A_FILE), (7, NOT_H_FILE), (-7, NOT_A_FILE), (-9, NOT_H_FILE)];
+
+#[inline]
+fn shift(board: u64, amount: i32) -> u64 {
+    if amount > 0 {
+        board << amount
+    } else {
+        board >> -amount
+    }
+}
+
+/// Computes sliding attacks with Kogge-Stone occluded fills, needing no tables at all.
+pub struct KoggeStoneAttackBoard {
+    slider_type: SliderType,
+    shifts: &'static [(i32, u64); 4],
+}
+
+impl KoggeStoneAttackBoard {
+    pub fn new(slider_type: SliderType) -> KoggeStoneAttackBoard {
+        let shifts = match slider_type {
+            SliderType::Rook => &ROOK_SHIFTS,
+            SliderType::Bishop => &BISHOP_SHIFTS,
+        };
+
+        KoggeStoneAttackBoard {
+            slider_type: slider_type,
+            shifts: shifts,
+        }
+    }
+
+    #[inline]
+    pub fn piece_type(&self) -> PieceType {
+        self.slider_type.piece_type()
+    }
+}
+
+/// Fills from `generator` along one ray until just before the first square not in `empty`, then
+/// shifts once more so the fill covers the attacked squares, including the blocker.
+fn kogge_stone_ray(generator: u64, empty: u64, amount: i32, wrap: u64) -> u64 {
+    let mut generator = generator;
+    let mut propagator = empty & wrap;
+
+    generator |= propagator & shift(generator, amount);
+    propagator &= shift(propagator, amount);
+    generator |= propagator & shift(generator, 2 * amount);
+    propagator &= shift(propagator, 2 * amount);
+    generator |= propagator & shift(generator, 4 * amount);
+
+    shift(generator, amount) & wrap
+}
+
+impl BitboardGenerator for KoggeStoneAttackBoard {
+    fn get_attacks(&self, position: SquarePosition, occupancy: BitBoard) -> BitBoard {
+        let BitBoard(square) = position.to_bit_board();
+        let BitBoard(empty) = !occupancy;
+
+        let attacks = self.shifts
+            .iter()
+            .fold(0, |attacks, &(amount, wrap)| {
+                attacks | kogge_stone_ray(square, empty, amount, wrap)
+            });
+
+        BitBoard(attacks)
+    }
+}
+
+/// Computes file and diagonal attacks with hyperbola quintessence, using byte swaps to reverse the
+/// lines, and rank attacks with a small table of first rank attacks.
+pub struct HyperbolaAttackBoard {
+    slider_type: SliderType,
+    line_masks: [[BitBoard; 3]; 64],
+    rank_attacks: [[u8; 64]; 8],
+}
+
+impl HyperbolaAttackBoard {
+    pub fn new(slider_type: SliderType) -> HyperbolaAttackBoard {
+        let lines = [[NORTH, SOUTH],
+                     [Direction(1, 1), Direction(-1, -1)],
+                     [Direction(1, -1), Direction(-1, 1)]];
+        let mut line_masks = [[EMPTY; 3]; 64];
+
+        for square_index in 0..64 {
+            for (line_index, directions) in lines.iter().enumerate() {
+                line_masks[square_index][line_index] =
+                    generate_mask(square_index, directions, true);
+            }
+        }
+
+        let mut rank_attacks = [[0u8; 64]; 8];
+
+        for file in 0..8 {
+            for inner in 0..64 {
+                let occupied = (inner << 1) as u8;
+                let mut attacks = 0u8;
+
+                for east in (file + 1)..8 {
+                    attacks |= 1 << east;
+                    if occupied & (1 << east) != 0 {
+                        break;
+                    }
+                }
+
+                for west in (0..file).rev() {
+                    attacks |= 1 << west;
+                    if occupied & (1 << west) != 0 {
+                        break;
+                    }
+                }
+
+                rank_attacks[file][inner] = attacks;
+            }
+        }
+
+        HyperbolaAttackBoard {
+            slider_type: slider_type,
+            line_masks: line_masks,
+            rank_attacks: rank_attacks,
+        }
+    }
+
+    #[inline]
+    pub fn piece_type(&self) -> PieceType {
+        self.slider_type.piece_type()
+    }
+
+    /// Attacks along a file or diagonal through `square`, which `mask` must exclude.
+    #[inline]
+    fn line_attacks(square: BitBoard, occupancy: BitBoard, mask: BitBoard) -> BitBoard {
+        let BitBoard(square) = square;
+        let BitBoard(forward) = occupancy & mask;
+        let reverse = forward.swap_bytes();
+
+        let forward = forward.wrapping_sub(square.wrapping_mul(2));
+        let reverse = reverse.wrapping_sub(square.swap_bytes().wrapping_mul(2));
+
+        BitBoard(forward ^ reverse.swap_bytes()) & mask
+    }
+
+    #[inline]
+    fn rank_attacks(&self, position: SquarePosition, occupancy: BitBoard) -> BitBoard {
+        let rank_shift = position.rank * 8;
+        let inner = ((occupancy.bits() >> (rank_shift + 1)) & 0x3f) as usize;
+
+        BitBoard((self.rank_attacks[position.file][inner] as u64) << rank_shift)
+    }
+}
+
+impl BitboardGenerator for HyperbolaAttackBoard {
+    fn get_attacks(&self, position: SquarePosition, occupancy: BitBoard) -> BitBoard {
+        let square = position.to_bit_board();
+        let masks = &self.line_masks[position.to_square_index()];
+
+        match self.slider_type {
+            SliderType::Rook => {
+                HyperbolaAttackBoard::line_attacks(square, occupancy, masks[0]) |
+                self.rank_attacks(position, occupancy)
+            }
+            SliderType::Bishop => {
+                HyperbolaAttackBoard::line_attacks(square, occupancy, masks[1]) |
+                HyperbolaAttackBoard::line_attacks(square, occupancy, masks[2])
+            }
+        }
+    }
+}
+
+/// Extracts the bits of `source` selected by `mask` and packs them into the low bits of the
+/// result, one bit at a time.
+pub fn software_pext(source: u64, mask: u64) -> u64 {
+    let mut mask = mask;
+    let mut result = 0;
+    let mut bit = 1;
+
+    while mask != 0 {
+        if source & mask & mask.wrapping_neg() != 0 {
+            result |= bit;
+        }
+        mask &= mask - 1;
+        bit <<= 1;
+    }
+
+    result
+}
+
+/// Parallel bit extraction, using the BMI2 instruction when the target has it (for example with
+/// `RUSTFLAGS="-C target-feature=+bmi2"`) and `software_pext` otherwise.
+#[cfg(all(target_arch = "x86_64", target_feature = "bmi2"))]
+#[inline]
+pub fn pext(source: u64, mask: u64) -> u64 {
+    let result: u64;
+    unsafe {
+        asm!("pextq $2, $1, $0"
+             : "=r"(result)
+             : "r"(source), "r"(mask));
+    }
+    result
+}
+
+/// Parallel bit extraction, using the BMI2 instruction when the target has it (for example with
+/// `RUSTFLAGS="-C target-feature=+bmi2"`) and `software_pext` otherwise.
+#[cfg(not(all(target_arch = "x86_64", target_feature = "bmi2")))]
+#[inline]
+pub fn pext(source: u64, mask: u64) -> u64 {
+    software_pext(source, mask)
+}
+
+/// Looks up sliding attacks in per square tables indexed by the relevant occupancy bits packed
+/// with `pext`, which takes the place of the magic multiply and shift.
+pub struct PextAttackBoard {
+    slider_type: SliderType,
+    masks: [BitBoard; 64],
+    offsets: [usize; 64],
+    attacks: Box<[BitBoard]>,
+}
+
+impl PextAttackBoard {
+    pub fn new(slider_type: SliderType) -> PextAttackBoard {
+        let directions: &[Direction] = match slider_type {
+            SliderType::Rook => &CARDINAL,
+            SliderType::Bishop => &INTERMEDIATE,
+        };
+
+        let mut masks = [EMPTY; 64];
+        let mut offsets = [0usize; 64];
+        let mut attacks = Vec::new();
+
+        for square_index in 0..64 {
+            let position = SquarePosition::try_from(square_index).unwrap();
+            masks[square_index] = generate_mask(square_index, directions, false);
+            offsets[square_index] = attacks.len();
+
+            // Subsets come out in the order of their packed bits, so each lands at its pext index.
+            attacks.extend(generate_sliding_attack_bitboards(masks[square_index],
+                                                             directions,
+                                                             position));
+        }
+
+        PextAttackBoard {
+            slider_type: slider_type,
+            masks: masks,
+            offsets: offsets,
+            attacks: attacks.into_boxed_slice(),
+        }
+    }
+
+    #[inline]
+    pub fn piece_type(&self) -> PieceType {
+        self.slider_type.piece_type()
+    }
+
+    #[inline]
+    pub fn attacks(&self) -> &[BitBoard] {
+        &self.attacks
+    }
+}
+
+impl BitboardGenerator for PextAttackBoard {
+    fn get_attacks(&self, position: SquarePosition, occupancy: BitBoard) -> BitBoard {
+        let square_index = position.to_square_index();
+
+        self.attacks[self.offsets[square_index] +
+                     pext(occupancy.bits(), self.masks[square_index].bits()) as usize]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use bit_boards::{BitBoard, EMPTY, FULL};
+    use move_gen::{BISHOP_ATTACKS_SIZE, BitboardGenerator, MagicAttackBoard, ROOK_ATTACKS_SIZE};
+    use piece::SliderType;
+    use rkiss::RKISS;
+    use sliding_attacks::{HyperbolaAttackBoard, KoggeStoneAttackBoard, PextAttackBoard, pext,
+                          software_pext};
+    use square_position::SquarePosition;
+    use test_helpers::square;
+
+    #[test]
+    fn software_pext_test() {
+        assert_eq!(software_pext(0, 0), 0);
+        assert_eq!(software_pext(!0, 0xf0f0), 0xff);
+        assert_eq!(software_pext(0b1010_0110, 0b1111_0000), 0b1010);
+        assert_eq!(software_pext(0x8000000000000001, 0x8000000000000001), 0b11);
+        assert_eq!(software_pext(0x1234, 0x0ff0), 0x23);
+
+        let mut rkiss = RKISS::new(7);
+        for _ in 0..1000 {
+            let source = rkiss.rand();
+            let mask = rkiss.rand();
+            assert_eq!(pext(source, mask), software_pext(source, mask));
+        }
+    }
+
+    #[test]
+    fn pext_table_size_test() {
+        assert_eq!(PextAttackBoard::new(SliderType::Rook).attacks().len(),
+                   ROOK_ATTACKS_SIZE);
+        assert_eq!(PextAttackBoard::new(SliderType::Bishop).attacks().len(),
+                   BISHOP_ATTACKS_SIZE);
+    }
+
+    #[test]
+    fn backends_agree_test() {
+        let mut rkiss = RKISS::new(11);
+
+        for &slider_type in [SliderType::Rook, SliderType::Bishop].iter() {
+            let magic = MagicAttackBoard::new(slider_type);
+            let kogge_stone = KoggeStoneAttackBoard::new(slider_type);
+            let hyperbola = HyperbolaAttackBoard::new(slider_type);
+            let pext = PextAttackBoard::new(slider_type);
+
+            for square_index in 0..64 {
+                let position = SquarePosition::try_from(square_index).unwrap();
+
+                for round in 0..200 {
+                    // Sparse occupancies reach the board edges, dense ones block early.
+                    let occupancy = BitBoard(match round % 3 {
+                        0 => rkiss.rand() & rkiss.rand() & rkiss.rand(),
+                        1 => rkiss.rand() & rkiss.rand(),
+                        _ => rkiss.rand(),
+                    });
+                    let expected = magic.get_attacks(position, occupancy);
+
+                    assert_eq!(kogge_stone.get_attacks(position, occupancy), expected);
+                    assert_eq!(hyperbola.get_attacks(position, occupancy), expected);
+                    assert_eq!(pext.get_attacks(position, occupancy), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_and_full_board_test() {
+        let position = square("d4");
+
+        let rook = HyperbolaAttackBoard::new(SliderType::Rook);
+        assert_eq!(rook.get_attacks(position, EMPTY), BitBoard(0x08080808f7080808));
+        assert_eq!(rook.get_attacks(position, FULL), BitBoard(0x0000000814080000));
+
+        let bishop = KoggeStoneAttackBoard::new(SliderType::Bishop);
+        assert_eq!(bishop.get_attacks(position, EMPTY), BitBoard(0x8041221400142241));
+        assert_eq!(bishop.get_attacks(position, FULL), BitBoard(0x0000001400140000));
+    }
+}
@@ -5,7 +5,7 @@ use std::convert::TryFrom;
 use error_types::FromStrError;
 use bit_boards::BitBoard;
 
-#[derive(Debug, Copy, Hash, Clone, PartialEq)]
+#[derive(Debug, Copy, Hash, Clone, PartialEq, Eq)]
 pub struct SquarePosition {
     pub rank: usize,
     pub file: usize,
@@ -24,7 +24,7 @@ impl SquarePosition {
     }
 
     pub fn to_bit_board(&self) -> BitBoard {
-        1 << self.to_square_index()
+        BitBoard(1 << self.to_square_index())
     }
 }
 
@@ -0,0 +1,23 @@
+//! Shorthands for the unit tests, which name squares the way they are written in FEN.
+
+use bit_boards::{BitBoard, EMPTY};
+use board_state::BoardState;
+use chess_move::Move;
+use square_position::SquarePosition;
+
+pub fn square(name: &str) -> SquarePosition {
+    name.parse().unwrap()
+}
+
+/// The board with just the named squares set.
+pub fn board(squares: &[&str]) -> BitBoard {
+    squares.iter().fold(EMPTY, |acc, name| acc | square(name).to_bit_board())
+}
+
+/// The legal move in `board` from `start` to `end`, panicking if there is none.
+pub fn find_move(board: &BoardState, start: &str, end: &str) -> Move {
+    board.legal_moves()
+        .into_iter()
+        .find(|chess_move| *chess_move.start() == square(start) && *chess_move.end() == square(end))
+        .unwrap()
+}
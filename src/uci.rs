@@ -0,0 +1,143 @@
+use board_state::BoardState;
+use chess_move::{Move, MoveType};
+use error_types::UciMoveError;
+use piece::PromotionType;
+use square_position::SquarePosition;
+
+/// Formats `chess_move` as a UCI coordinate string. Castling is written as the king's move to its
+/// destination, or as the king capturing its own rook when `board` is a Chess960 position.
+pub fn format_uci(board: &BoardState, chess_move: &Move) -> String {
+    let end = match *chess_move.move_type() {
+        MoveType::Castle(castle_type) if board.is_chess960() => {
+            board.castling_rooks().position(chess_move.active().color(), castle_type)
+        }
+        _ => *chess_move.end(),
+    };
+
+    let mut uci = chess_move.start().to_string() + &end.to_string();
+
+    match *chess_move.move_type() {
+        MoveType::Promotion(promotion) |
+        MoveType::CapturePromotion(promotion, _) => {
+            uci += &promotion.piece_type().to_string().to_lowercase();
+        }
+        _ => {}
+    }
+
+    uci
+}
+
+/// Resolves a UCI coordinate string against the legal moves of `board`. Castling is accepted as
+/// the king capturing its own rook, and also as the king's move to its destination in standard
+/// chess.
+pub fn parse_uci(board: &BoardState, uci: &str) -> Result<Move, UciMoveError> {
+    if uci.bytes().any(|byte| byte >= 0x80) || (uci.len() != 4 && uci.len() != 5) {
+        return Err(UciMoveError::MalformedInput(uci.to_owned()));
+    }
+
+    let start: SquarePosition = uci[0..2].parse()
+        .map_err(|_| UciMoveError::MalformedInput(uci.to_owned()))?;
+    let end: SquarePosition = uci[2..4].parse()
+        .map_err(|_| UciMoveError::MalformedInput(uci.to_owned()))?;
+    let promotion = match &uci[4..] {
+        "" => None,
+        "q" => Some(PromotionType::Queen),
+        "r" => Some(PromotionType::Rook),
+        "b" => Some(PromotionType::Bishop),
+        "n" => Some(PromotionType::Knight),
+        _ => return Err(UciMoveError::MalformedInput(uci.to_owned())),
+    };
+
+    board.legal_moves()
+        .into_iter()
+        .find(|chess_move| {
+            if *chess_move.start() != start {
+                return false;
+            }
+
+            match *chess_move.move_type() {
+                MoveType::Castle(castle_type) => {
+                    let rook = board.castling_rooks()
+                        .position(chess_move.active().color(), castle_type);
+                    promotion.is_none() &&
+                    (end == rook || (!board.is_chess960() && end == *chess_move.end()))
+                }
+                MoveType::Promotion(move_promotion) |
+                MoveType::CapturePromotion(move_promotion, _) => {
+                    end == *chess_move.end() && promotion == Some(move_promotion)
+                }
+                _ => end == *chess_move.end() && promotion.is_none(),
+            }
+        })
+        .ok_or_else(|| UciMoveError::IllegalMove(uci.to_owned()))
+}
+
+#[cfg(test)]
+mod test {
+    use board_state::BoardState;
+    use castling::CastleType;
+    use chess_move::MoveType;
+    use error_types::UciMoveError;
+    use uci::{format_uci, parse_uci};
+
+    #[test]
+    fn parse_uci_test() {
+        let board: BoardState = "r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1".parse().unwrap();
+
+        let castle = parse_uci(&board, "e1g1").unwrap();
+        assert_eq!(*castle.move_type(), MoveType::Castle(CastleType::Kingside));
+        assert_eq!(parse_uci(&board, "e1a1").unwrap(),
+                   parse_uci(&board, "e1c1").unwrap());
+
+        let promotion = parse_uci(&board, "b7a8n").unwrap();
+        assert_eq!(format_uci(&board, &promotion), "b7a8n");
+
+        match parse_uci(&board, "b7b8") {
+            Err(UciMoveError::IllegalMove(_)) => {}
+            _ => panic!("expected b7b8 to be illegal without a promotion"),
+        }
+
+        match parse_uci(&board, "e1g") {
+            Err(UciMoveError::MalformedInput(_)) => {}
+            _ => panic!("expected e1g to be malformed"),
+        }
+
+        match parse_uci(&board, "e1i1") {
+            Err(UciMoveError::MalformedInput(_)) => {}
+            _ => panic!("expected e1i1 to be malformed"),
+        }
+    }
+
+    #[test]
+    fn chess960_castling_test() {
+        let board: BoardState = "1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1".parse().unwrap();
+
+        let castle = parse_uci(&board, "g1h1").unwrap();
+        assert_eq!(*castle.move_type(), MoveType::Castle(CastleType::Kingside));
+        assert_eq!(format_uci(&board, &castle), "g1h1");
+
+        let castle = parse_uci(&board, "g1b1").unwrap();
+        assert_eq!(*castle.move_type(), MoveType::Castle(CastleType::Queenside));
+        assert_eq!(format_uci(&board, &castle), "g1b1");
+
+        let king_move = parse_uci(&board, "g1f1").unwrap();
+        assert_eq!(king_move.end().to_string(), "f1");
+        assert!(parse_uci(&board, "g1c1").is_err());
+    }
+
+    #[test]
+    fn uci_round_trip_test() {
+        let fen_strings = ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                           "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                           "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"];
+
+        for fen_string in fen_strings.iter() {
+            let board: BoardState = fen_string.parse().unwrap();
+
+            for chess_move in board.legal_moves() {
+                let uci = format_uci(&board, &chess_move);
+                assert_eq!(parse_uci(&board, &uci).unwrap(), chess_move, "{}", uci);
+            }
+        }
+    }
+}
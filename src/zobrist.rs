@@ -0,0 +1,208 @@
+use bit_boards::bit_scan_forward;
+use board_state::BoardState;
+use castling::{BLACK_KINGSIDE, BLACK_QUEENSIDE, CastlingFlags, WHITE_KINGSIDE, WHITE_QUEENSIDE};
+use piece::{Color, Piece, PieceType};
+use rkiss::RKISS;
+use square_position::{EAST, NORTH, SOUTH, SquarePosition, WEST};
+
+const ZOBRIST_SEED_ROUNDS: usize = 73;
+
+lazy_static! {
+    pub static ref ZOBRIST_KEYS: ZobristKeys = ZobristKeys::new(ZOBRIST_SEED_ROUNDS);
+}
+
+pub struct ZobristKeys {
+    pieces: [[[u64; 64]; 6]; 2],
+    castling: [u64; 16],
+    en_passant: [u64; 8],
+    side: u64,
+}
+
+impl ZobristKeys {
+    pub fn new(rounds: usize) -> ZobristKeys {
+        let mut rkiss = RKISS::new(rounds);
+        let mut keys = ZobristKeys {
+            pieces: [[[0; 64]; 6]; 2],
+            castling: [0; 16],
+            en_passant: [0; 8],
+            side: 0,
+        };
+
+        for color_keys in keys.pieces.iter_mut() {
+            for piece_keys in color_keys.iter_mut() {
+                for square_key in piece_keys.iter_mut() {
+                    *square_key = rkiss.rand();
+                }
+            }
+        }
+
+        // Each combination of rights hashes to the xor of its individual flags, so that losing
+        // one right changes the key the same way regardless of the others.
+        let flag_keys = [(WHITE_QUEENSIDE, rkiss.rand()),
+                         (WHITE_KINGSIDE, rkiss.rand()),
+                         (BLACK_QUEENSIDE, rkiss.rand()),
+                         (BLACK_KINGSIDE, rkiss.rand())];
+        for (bits, castling_key) in keys.castling.iter_mut().enumerate() {
+            let rights = CastlingFlags::from_bits_truncate(bits as u8);
+            *castling_key = flag_keys.iter()
+                .filter(|&&(flag, _)| rights.contains(flag))
+                .fold(0, |acc, &(_, flag_key)| acc ^ flag_key);
+        }
+
+        for file_key in keys.en_passant.iter_mut() {
+            *file_key = rkiss.rand();
+        }
+
+        keys.side = rkiss.rand();
+
+        keys
+    }
+
+    #[inline]
+    pub fn piece(&self, piece: Piece, position: SquarePosition) -> u64 {
+        let square_index = position.to_square_index();
+        self.pieces[piece.color() as usize][piece.piece_type() as usize][square_index]
+    }
+
+    #[inline]
+    pub fn castling(&self, rights: CastlingFlags) -> u64 {
+        self.castling[rights.bits() as usize]
+    }
+
+    #[inline]
+    pub fn en_passant(&self, file: usize) -> u64 {
+        self.en_passant[file]
+    }
+
+    #[inline]
+    pub fn side(&self) -> u64 {
+        self.side
+    }
+}
+
+/// The file of the en passant square, but only when a pawn of the side to move is actually
+/// placed to capture on it. Positions that differ only by an unusable en passant square are the
+/// same position for repetition purposes.
+pub fn en_passant_file(board: &BoardState) -> Option<usize> {
+    let en_passant = board.en_passant();
+    let behind = match board.active_color() {
+        Color::White => SOUTH,
+        Color::Black => NORTH,
+    };
+    let capturers = (en_passant.shift(EAST) | en_passant.shift(WEST)).shift(behind);
+
+    if !(capturers & board[(board.active_color(), PieceType::Pawn)]).is_empty() {
+        bit_scan_forward(en_passant).map(|square_index| square_index % 8)
+    } else {
+        None
+    }
+}
+
+/// Computes the key of a position from scratch, ignoring any incrementally maintained state.
+pub fn compute_key(board: &BoardState) -> u64 {
+    let keys = &*ZOBRIST_KEYS;
+    let mut key = board.piece_board()
+        .into_iter()
+        .fold(0, |acc, (position, piece)| acc ^ keys.piece(piece, position));
+
+    key ^= keys.castling(board.castling_rights());
+
+    if let Some(file) = en_passant_file(board) {
+        key ^= keys.en_passant(file);
+    }
+
+    if board.active_color() == Color::Black {
+        key ^= keys.side();
+    }
+
+    key
+}
+
+/// Computes the key of the pawns of both colors from scratch, ignoring every other piece and all
+/// of the other state.
+pub fn compute_pawn_key(board: &BoardState) -> u64 {
+    let keys = &*ZOBRIST_KEYS;
+
+    board.piece_board()
+        .into_iter()
+        .filter(|&(_, piece)| piece.piece_type() == PieceType::Pawn)
+        .fold(0, |acc, (position, piece)| acc ^ keys.piece(piece, position))
+}
+
+#[cfg(test)]
+mod test {
+    use board_state::BoardState;
+    use test_helpers::find_move;
+    use zobrist::{compute_key, compute_pawn_key};
+
+    fn play(board: &mut BoardState, moves: &[(&str, &str)]) {
+        for &(start, end) in moves {
+            let chess_move = find_move(board, start, end);
+            board.make_move(&chess_move);
+        }
+    }
+
+    #[test]
+    fn transposition_same_key_test() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let mut first: BoardState = start.parse().unwrap();
+        let mut second: BoardState = start.parse().unwrap();
+
+        play(&mut first, &[("g1", "f3"), ("g8", "f6"), ("b1", "c3")]);
+        play(&mut second, &[("b1", "c3"), ("g8", "f6"), ("g1", "f3")]);
+
+        assert_eq!(first.key(), second.key());
+        assert_eq!(first.key(), compute_key(&first));
+    }
+
+    #[test]
+    fn make_unmake_restores_key_test() {
+        let mut board: BoardState =
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+        let original_key = board.key();
+
+        for chess_move in board.legal_moves() {
+            board.make_move(&chess_move);
+            assert_eq!(board.key(), compute_key(&board));
+            assert!(board.key() != original_key);
+            board.unmake_move(&chess_move);
+
+            assert_eq!(board.key(), original_key);
+        }
+    }
+
+    #[test]
+    fn unusable_en_passant_ignored_test() {
+        let with_square: BoardState =
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".parse().unwrap();
+        let without_square: BoardState =
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1".parse().unwrap();
+        assert_eq!(with_square.key(), without_square.key());
+
+        let capturable: BoardState =
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3".parse().unwrap();
+        let not_capturable: BoardState =
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3".parse().unwrap();
+        assert!(capturable.key() != not_capturable.key());
+    }
+
+    #[test]
+    fn pawn_key_test() {
+        let mut board: BoardState =
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+        let original_pawn_key = board.pawn_key();
+
+        for chess_move in board.legal_moves() {
+            board.make_move(&chess_move);
+            assert_eq!(board.pawn_key(), compute_pawn_key(&board));
+            board.unmake_move(&chess_move);
+
+            assert_eq!(board.pawn_key(), original_pawn_key);
+        }
+
+        let same_pawns: BoardState =
+            "4k3/p1pp1p2/4p1p1/3P4/1p2P3/7p/PPP2PPP/4K3 b - - 0 1".parse().unwrap();
+        assert_eq!(same_pawns.pawn_key(), original_pawn_key);
+        assert!(same_pawns.key() != board.key());
+    }
+}
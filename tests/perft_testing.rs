@@ -0,0 +1,83 @@
+extern crate boknafisk;
+
+use self::boknafisk::board_state::*;
+
+fn check_perft(fen_string: &str, expected_nodes: &[u64]) {
+    let mut board_state: BoardState = fen_string.parse().unwrap();
+
+    for (depth, &expected) in expected_nodes.iter().enumerate() {
+        let depth = (depth + 1) as u32;
+        assert_eq!(board_state.perft(depth),
+                   expected,
+                   "perft({}) of {}",
+                   depth,
+                   fen_string);
+    }
+
+    assert_eq!(board_state.to_string(), fen_string);
+}
+
+#[test]
+fn perft_starting_position() {
+    check_perft("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                &[20, 400, 8902, 197281]);
+}
+
+#[test]
+fn perft_kiwipete() {
+    check_perft("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                &[48, 2039, 97862]);
+}
+
+#[test]
+fn perft_en_passant_position() {
+    check_perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                &[14, 191, 2812, 43238]);
+}
+
+#[test]
+fn perft_promotion_position() {
+    check_perft("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                &[6, 264, 9467]);
+}
+
+#[test]
+fn perft_promotion_position_mirrored() {
+    check_perft("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+                &[6, 264, 9467]);
+}
+
+#[test]
+fn perft_discovered_promotion_position() {
+    check_perft("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                &[44, 1486, 62379]);
+}
+
+#[test]
+fn perft_middlegame_position() {
+    check_perft("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+                &[46, 2079, 89890]);
+}
+
+#[test]
+fn divide_sums_to_perft() {
+    let mut board_state: BoardState =
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+
+    let divisions = board_state.divide(2);
+
+    assert_eq!(divisions.len(), 48);
+    assert_eq!(divisions.iter().map(|&(_, nodes)| nodes).sum::<u64>(), 2039);
+}
+
+#[test]
+fn perft_chess960_positions() {
+    check_perft("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                &[21, 528, 12189]);
+    check_perft("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                &[21, 807, 18002]);
+    check_perft("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+                &[20, 479, 10471]);
+    check_perft("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+                &[22, 593, 13440]);
+}